@@ -0,0 +1,17 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rusterio::{DatasetReader, Sentinel2};
+
+const TEST_DATA: &str =
+    "data/S2B_MSIL2A_20241126T093239_N0511_R136_T33PTM_20241126T120342.SAFE.zip";
+
+fn open_product(c: &mut Criterion) {
+    let mut group = c.benchmark_group("open_product");
+    group.bench_function("probe", |b| b.iter(|| Sentinel2::probe(TEST_DATA).unwrap()));
+    group.bench_function("raster_from", |b| {
+        b.iter(|| Sentinel2::raster_from(TEST_DATA).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, open_product);
+criterion_main!(benches);
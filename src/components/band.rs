@@ -1,46 +1,244 @@
-use gdal::Metadata as GdalMetadata;
-use rasters::prelude::{transform_from_gdal, PixelTransform, RasterPathReader};
+use gdal::{raster::GdalDataType, spatial_ref::SpatialRef, Metadata as GdalMetadata};
+use itertools::Itertools;
+use ndarray::Array2;
+use rasters::{
+    prelude::{transform_from_gdal, PixelTransform, RasterPathReader},
+    reader::{ChunkReader, DatasetReader},
+};
 use std::{
     collections::{hash_map::Entry, HashMap},
-    path::PathBuf,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
 };
 
 use super::{Result, Sentinel2ArrayError};
+#[cfg(feature = "serde")]
+use super::raster::transform_to_gdal;
+#[cfg(feature = "serde")]
+use gdal::GeoTransform;
 
 #[derive(Debug)]
 pub struct BandGroup {
-    gdal_dataset_path: PathBuf,
+    /// GDAL name of the dataset as GDAL describes it, kept verbatim so that
+    /// subdataset names and virtual file system paths like `/vsizip/...` or
+    /// `/vsis3/...` reopen as they were.
+    gdal_dataset_path: String,
     crs: String,
     geo_transform: PixelTransform,
-    raster_size: (usize, usize)
+    raster_size: (usize, usize),
+    no_data_value: Option<f64>,
+    /// GDAL data types of the bands, by band index less one.
+    data_types: Vec<GdalDataType>,
+    /// GDAL open options the dataset gets reopened with, like `KEY=VALUE`.
+    open_options: Vec<String>,
+    /// Datasets kept open between reads. GDAL datasets may only be used by
+    /// one thread at a time, so each read takes one out for itself and puts
+    /// it back after, opening another if all are in use: parallel reads of
+    /// the group end up with about one dataset per thread reading it.
+    datasets: Mutex<Vec<gdal::Dataset>>,
+    /// Number of datasets opened for `datasets`.
+    datasets_opened: AtomicUsize,
+}
+
+/// Clones start with no datasets of their own open.
+impl Clone for BandGroup {
+    fn clone(&self) -> Self {
+        Self {
+            gdal_dataset_path: self.gdal_dataset_path.clone(),
+            crs: self.crs.clone(),
+            geo_transform: self.geo_transform,
+            raster_size: self.raster_size,
+            no_data_value: self.no_data_value,
+            data_types: self.data_types.clone(),
+            open_options: self.open_options.clone(),
+            datasets: Mutex::default(),
+            datasets_opened: AtomicUsize::new(0),
+        }
+    }
 }
 
 impl BandGroup {
     pub fn new(dataset: &gdal::Dataset) -> Result<Self> {
-        let gdal_dataset_path = dataset.description()?.into();
+        let gdal_dataset_path = dataset.description()?;
         let crs = dataset.projection();
+        let no_data_value = dataset.rasterband(1)?.no_data_value();
+        let data_types = dataset
+            .rasterbands()
+            .map(|raster_band| raster_band.map(|raster_band| raster_band.band_type()))
+            .collect::<std::result::Result<_, _>>()?;
         dataset
             .geo_transform()
             .map(|geo_transform| Self {
                 gdal_dataset_path,
                 crs,
                 geo_transform: transform_from_gdal(&geo_transform),
-                raster_size: dataset.raster_size()
+                raster_size: dataset.raster_size(),
+                no_data_value,
+                data_types,
+                open_options: Vec::new(),
+                datasets: Mutex::default(),
+                datasets_opened: AtomicUsize::new(0),
             })
             .map_err(Sentinel2ArrayError::GdalError)
     }
 
-    fn band_reader(&self, band_index: usize) -> RasterPathReader<PathBuf> {
+    /// Set the GDAL open options, like `KEY=VALUE`, the dataset gets reopened
+    /// with for reads. `BandInfo::reader` reopens it without them.
+    pub fn with_open_options(mut self, open_options: &[&str]) -> Self {
+        self.open_options = open_options.iter().map(|option| option.to_string()).collect();
+        self
+    }
+
+    /// Reopen the dataset, with its open options.
+    fn open(&self) -> Result<gdal::Dataset> {
+        Ok(self.open_with_flags(gdal::GdalOpenFlags::default())?)
+    }
+
+    fn open_with_flags(
+        &self,
+        open_flags: gdal::GdalOpenFlags,
+    ) -> gdal::errors::Result<gdal::Dataset> {
+        let open_options = self.open_options.iter().map(String::as_str).collect::<Vec<_>>();
+        let options = gdal::DatasetOptions {
+            open_flags,
+            open_options: Some(&open_options),
+            ..Default::default()
+        };
+        gdal::Dataset::open_ex(&self.gdal_dataset_path, options)
+    }
+
+    /// Build overviews of the bands at `band_indices` into the dataset, see
+    /// `Raster::build_overviews`. The datasets kept open for reads get closed,
+    /// since they wouldn't see the overviews.
+    fn build_overviews(
+        &self,
+        band_indices: &[i32],
+        levels: &[i32],
+        resampling: &str,
+    ) -> Result<()> {
+        let open_flags = gdal::GdalOpenFlags::GDAL_OF_UPDATE | gdal::GdalOpenFlags::GDAL_OF_RASTER;
+        let mut dataset = self.open_with_flags(open_flags).map_err(|_| {
+            Sentinel2ArrayError::OverviewsNotWritable(self.gdal_dataset_path.clone())
+        })?;
+        dataset.build_overviews(resampling, levels, band_indices)?;
+        self.clear_cache();
+        Ok(())
+    }
+
+    fn band_reader(&self, band_index: usize) -> RasterPathReader<'_, String> {
         RasterPathReader(&self.gdal_dataset_path, band_index)
     }
+
+    /// GDAL name the dataset gets reopened with.
+    pub fn gdal_dataset_path(&self) -> &str {
+        &self.gdal_dataset_path
+    }
+
+    /// Number of bands of the group's dataset.
+    pub fn band_count(&self) -> usize {
+        self.data_types.len()
+    }
+
+    fn open_band_reader(&self, band_index: usize) -> Result<DatasetReader> {
+        Ok(DatasetReader(self.open()?, band_index))
+    }
+
+    /// Read a window of the band at `band_index` with one of the cached
+    /// datasets, see `datasets`.
+    fn read_cached<T: gdal::raster::GdalType + Copy>(
+        &self,
+        band_index: usize,
+        off: (isize, isize),
+        size: (usize, usize),
+    ) -> Result<Array2<T>> {
+        let cached = self.datasets.lock().unwrap_or_else(PoisonError::into_inner).pop();
+        let dataset = match cached {
+            Some(dataset) => dataset,
+            None => {
+                let dataset = self.open()?;
+                self.datasets_opened.fetch_add(1, Ordering::Relaxed);
+                dataset
+            }
+        };
+        let reader = DatasetReader(dataset, band_index);
+        let array = reader.read_as_array(off, size);
+        self.datasets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(reader.0);
+        array.map_err(Sentinel2ArrayError::RastersError)
+    }
+
+    fn clear_cache(&self) {
+        self.datasets.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    }
 }
 
-#[derive(Debug)]
+/// What a [`BandGroup`] knows of its dataset, to rebuild it without opening
+/// the dataset, see `Raster::save_index`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct BandGroupLayout {
+    pub gdal_dataset_path: String,
+    pub crs: String,
+    pub geo_transform: GeoTransform,
+    pub raster_size: (usize, usize),
+    pub no_data_value: Option<f64>,
+    /// GDAL names of the bands' data types, like `UInt16`.
+    pub data_types: Vec<String>,
+    pub open_options: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+impl BandGroup {
+    pub(crate) fn layout(&self) -> BandGroupLayout {
+        BandGroupLayout {
+            gdal_dataset_path: self.gdal_dataset_path.clone(),
+            crs: self.crs.clone(),
+            geo_transform: transform_to_gdal(&self.geo_transform),
+            raster_size: self.raster_size,
+            no_data_value: self.no_data_value,
+            data_types: self.data_types.iter().map(GdalDataType::name).collect(),
+            open_options: self.open_options.clone(),
+        }
+    }
+
+    pub(crate) fn from_layout(layout: BandGroupLayout) -> Result<Self> {
+        Ok(Self {
+            gdal_dataset_path: layout.gdal_dataset_path,
+            crs: layout.crs,
+            geo_transform: transform_from_gdal(&layout.geo_transform),
+            raster_size: layout.raster_size,
+            no_data_value: layout.no_data_value,
+            data_types: layout
+                .data_types
+                .iter()
+                .map(|name| GdalDataType::from_name(name))
+                .collect::<std::result::Result<_, _>>()?,
+            open_options: layout.open_options,
+            datasets: Mutex::default(),
+            datasets_opened: AtomicUsize::new(0),
+        })
+    }
+}
+
+/// Summary statistics of a band's values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+#[derive(Debug, Clone)]
 pub struct BandInfo<BM> {
     index: usize,
     group: Arc<BandGroup>,
     metadata: BM,
+    calibration: Option<(f64, f64)>,
 }
 
 impl<BM> BandInfo<BM> {
@@ -49,51 +247,257 @@ impl<BM> BandInfo<BM> {
             index,
             group,
             metadata,
+            calibration: None,
         }
     }
 
+    /// Set the band's GDAL scale and offset, see `calibration`.
+    pub fn with_calibration(mut self, calibration: Option<(f64, f64)>) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Copy of this band info sharing its group, without the metadata.
+    pub fn detached(&self) -> BandInfo<()> {
+        BandInfo::new(Arc::clone(&self.group), self.index, ()).with_calibration(self.calibration)
+    }
+
+    /// GDAL `(scale, offset)` turning the band's values into physical ones,
+    /// if it has any other than the identity.
+    pub fn calibration(&self) -> Option<(f64, f64)> {
+        self.calibration
+    }
+
+    pub fn metadata(&self) -> &BM {
+        &self.metadata
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn group(&self) -> &Arc<BandGroup> {
+        &self.group
+    }
+
+    /// 1 based index of the band in its group's dataset.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     pub fn geo_transform(&self) -> PixelTransform {
         self.group.geo_transform
     }
 
+    pub fn crs(&self) -> &str {
+        &self.group.crs
+    }
+
     pub fn raster_size(&self) -> (usize, usize) {
         self.group.raster_size
     }
 
+    pub fn no_data_value(&self) -> Option<f64> {
+        self.group.no_data_value
+    }
+
+    /// Order of the bands' grids, finest first, then by origin, left to right
+    /// and top to bottom, so that picking the finest of bands with the same
+    /// resolution doesn't depend on their order.
+    pub fn grid_order(&self, other: &Self) -> std::cmp::Ordering {
+        let (transform, other_transform) = (self.geo_transform(), other.geo_transform());
+        transform
+            .m11
+            .total_cmp(&other_transform.m11)
+            .then(transform.m13.total_cmp(&other_transform.m13))
+            .then(other_transform.m23.total_cmp(&transform.m23))
+    }
+
     pub fn resolution(&self) -> u8 {
         self.geo_transform().m11 as u8
     }
 
-    pub fn reader(&self) -> RasterPathReader<PathBuf> {
+    /// GDAL data type of the band's pixels.
+    pub fn data_type(&self) -> GdalDataType {
+        self.group
+            .data_types
+            .get(self.index - 1)
+            .copied()
+            .unwrap_or(GdalDataType::Unknown)
+    }
+
+    /// GDAL name of the (sub)dataset the band is read from.
+    pub fn gdal_dataset_path(&self) -> &str {
+        self.group.gdal_dataset_path()
+    }
+
+    /// Order of preference among bands of the same name, lowest first, see
+    /// `Bands::insert`.
+    fn preference(&self) -> (u8, &str, usize) {
+        (self.resolution(), self.gdal_dataset_path(), self.index)
+    }
+
+    pub fn reader(&self) -> RasterPathReader<'_, String> {
         self.group.band_reader(self.index)
     }
+
+    /// Reader holding the band's dataset open, unlike `reader` which reopens
+    /// it on every read.
+    pub fn open_reader(&self) -> Result<DatasetReader> {
+        self.group.open_band_reader(self.index)
+    }
+
+    /// GDAL's natural block size of the band, in pixels: the tile size of
+    /// tiled rasters, a single row (or a few) of striped ones.
+    pub fn block_size(&self) -> Result<(usize, usize)> {
+        let reader = self.open_reader()?;
+        Ok(reader.0.rasterband(reader.1)?.block_size())
+    }
+
+    /// Number of overviews of the band in its dataset.
+    pub fn overview_count(&self) -> Result<usize> {
+        let reader = self.open_reader()?;
+        Ok(reader.0.rasterband(reader.1)?.overview_count()? as usize)
+    }
+
+    /// Read a window of the band, reusing the datasets its group keeps open.
+    pub fn read_cached<T: gdal::raster::GdalType + Copy>(
+        &self,
+        off: (isize, isize),
+        size: (usize, usize),
+    ) -> Result<Array2<T>> {
+        self.group.read_cached(self.index, off, size)
+    }
 }
 
-#[derive(Debug, Default)]
+/// GDAL `(scale, offset)` of `raster_band`, `None` if it has neither.
+pub fn gdal_calibration(raster_band: &gdal::raster::RasterBand) -> Option<(f64, f64)> {
+    match (raster_band.scale(), raster_band.offset()) {
+        (None, None) => None,
+        (scale, offset) => Some((scale.unwrap_or(1.), offset.unwrap_or(0.))),
+    }
+    .filter(|&calibration| calibration != (1., 0.))
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Bands<BM>(HashMap<String, BandInfo<BM>>);
 
 impl<BM> Bands<BM> {
-    pub fn get(&self, band_name: &'static str) -> Result<&BandInfo<BM>> {
+    pub fn get(&self, band_name: &str) -> Result<&BandInfo<BM>> {
         self.0
             .get(band_name)
             .ok_or(Sentinel2ArrayError::BandNotFound(band_name.into()))
     }
     
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &BandInfo<BM>)> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Number of distinct groups the bands share.
+    pub fn group_count(&self) -> usize {
+        self.0
+            .values()
+            .map(|band_info| Arc::as_ptr(&band_info.group))
+            .unique()
+            .count()
+    }
+
+    /// Close the datasets the bands' groups keep open between reads.
+    pub fn clear_cache(&self) {
+        self.0.values().for_each(|band_info| band_info.group.clear_cache());
+    }
+
+    /// Build overviews of the bands at the decimation factors `levels`, one
+    /// dataset at a time, with GDAL's `resampling` method like `NEAREST`.
+    pub(crate) fn build_overviews(&self, levels: &[u32], resampling: &str) -> Result<()> {
+        let levels = levels.iter().map(|&level| level as i32).collect::<Vec<_>>();
+        for (_, group_bands) in self
+            .0
+            .values()
+            .into_group_map_by(|band_info| Arc::as_ptr(&band_info.group))
+        {
+            let band_indices = group_bands
+                .iter()
+                .map(|band_info| band_info.index as i32)
+                .sorted()
+                .dedup()
+                .collect::<Vec<_>>();
+            group_bands[0].group.build_overviews(&band_indices, &levels, resampling)?;
+        }
+        Ok(())
+    }
+
+    /// Number of datasets the bands' groups opened for their caches.
+    pub(crate) fn datasets_opened(&self) -> usize {
+        self.0
+            .values()
+            .unique_by(|band_info| Arc::as_ptr(&band_info.group))
+            .map(|band_info| band_info.group.datasets_opened.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Whether all bands are in the projection of the finest one, going by
+    /// GDAL's notion of equivalent spatial references rather than WKT strings.
+    pub fn share_projection(&self) -> Result<bool> {
+        let Some(finest) = self
+            .0
+            .values()
+            .min_by(|a, b| a.grid_order(b))
+        else {
+            return Ok(true);
+        };
+        let canonical = SpatialRef::from_wkt(finest.crs())?;
+        for band_info in self.0.values() {
+            let crs = band_info.crs();
+            if crs != finest.crs() && SpatialRef::from_wkt(crs)? != canonical {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Band names in natural order, e.g. `B1, B2, ..., B8, B8A, B9, B11, B12`,
+    /// followed by the names without a number (`AOT`, `SCL`, ...) in
+    /// lexicographic order.
     pub fn names(&self) -> Vec<&String> {
-        let mut names = self.0.keys().collect::<Vec<&String>>();
-        names.sort();
-        names
+        self.iter_sorted().map(|(band_name, _)| band_name).collect()
+    }
+
+    /// Bands in the order of `names`.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&String, &BandInfo<BM>)> {
+        self.0.iter().sorted_by_key(|(band_name, _)| natural_key(band_name))
     }
 
+    /// Add `band_info` as `band_name`, unless a band of that name is already
+    /// there with precedence. The finer band wins, and among bands of the same
+    /// resolution the one of the lexicographically smaller dataset path then
+    /// band index, so the outcome doesn't depend on the insertion order.
     fn insert(mut self, band_name: String, band_info: BandInfo<BM>) -> Self {
         match self.0.entry(band_name) {
-            Entry::Occupied(entry) if entry.get().resolution() < band_info.resolution() => entry,
+            Entry::Occupied(entry) if entry.get().preference() <= band_info.preference() => entry,
             entry => entry.insert_entry(band_info),
         };
         self
     }
 }
 
+/// Sort key of `band_name`, with the numbered ones like `B8A` first, by
+/// prefix, number and suffix.
+fn natural_key(band_name: &str) -> (bool, &str, u32, &str) {
+    let numbered = band_name
+        .find(|c: char| c.is_ascii_digit())
+        .filter(|&start| start > 0)
+        .and_then(|start| {
+            let end = band_name[start..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(band_name.len(), |end| start + end);
+            let number = band_name[start..end].parse().ok()?;
+            Some((false, &band_name[..start], number, &band_name[end..]))
+        });
+    numbered.unwrap_or((true, band_name, 0, ""))
+}
+
 impl<BM: Default> FromIterator<(String, BandInfo<BM>)> for Bands<BM> {
     fn from_iter<T: IntoIterator<Item = (String, BandInfo<BM>)>>(iter: T) -> Self {
         iter.into_iter()
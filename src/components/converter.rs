@@ -0,0 +1,15 @@
+/// Conversion of the digital numbers of a band into physical values,
+/// see `Raster::read_bands_convert`.
+pub trait PixelConverter {
+    fn convert(&self, band_name: &str, value: u16) -> f32;
+}
+
+/// Keeps digital numbers as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl PixelConverter for Identity {
+    fn convert(&self, _band_name: &str, value: u16) -> f32 {
+        value as f32
+    }
+}
@@ -1,7 +1,19 @@
-use super::{Result, Sentinel2ArrayError};
-use std::collections::HashMap;
+use super::{band::BandStats, Result, Sentinel2ArrayError};
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
 
-#[derive(Debug, Default)]
+/// Item whose value differs between two metadata, `None` where it's absent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataDelta {
+    pub key: String,
+    pub self_value: Option<String>,
+    pub other_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metadata {
     description: String,
     hashmap: HashMap<String, String>,
@@ -15,10 +27,18 @@ impl Metadata {
         }
     }
 
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
     pub fn insert(&mut self, key: String, value: String) {
         self.hashmap.insert(key, value);
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.hashmap.iter()
+    }
+
     pub fn get(&self, key: &str) -> Result<&String> {
         self.hashmap
             .get(key)
@@ -27,4 +47,49 @@ impl Metadata {
                 key: key.into(),
             })
     }
+
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Result<T> {
+        self.get_with(key, |value| value.trim().parse().ok())
+    }
+
+    pub fn get_with<T>(&self, key: &str, parse: impl FnOnce(&str) -> Option<T>) -> Result<T> {
+        let value = self.get(key)?;
+        parse(value).ok_or_else(|| Sentinel2ArrayError::MetadataValueNotParsable {
+            object_desc: self.description.clone(),
+            key: key.into(),
+            value: value.clone(),
+        })
+    }
+
+    /// Statistics GDAL precomputed into the `STATISTICS_*` items, if any.
+    pub fn cached_statistics(&self) -> Result<Option<BandStats>> {
+        if !self.hashmap.contains_key("STATISTICS_MEAN") {
+            return Ok(None);
+        }
+        Ok(Some(BandStats {
+            min: self.get_parsed("STATISTICS_MINIMUM")?,
+            max: self.get_parsed("STATISTICS_MAXIMUM")?,
+            mean: self.get_parsed("STATISTICS_MEAN")?,
+            std_dev: self.get_parsed("STATISTICS_STDDEV")?,
+        }))
+    }
+
+    /// Items that differ from, or are missing in, `other`, sorted by key.
+    pub fn diff(&self, other: &Metadata) -> Vec<MetadataDelta> {
+        let keys = self
+            .hashmap
+            .keys()
+            .chain(other.hashmap.keys())
+            .collect::<BTreeSet<_>>();
+        keys.into_iter()
+            .filter_map(|key| {
+                let (self_value, other_value) = (self.hashmap.get(key), other.hashmap.get(key));
+                (self_value != other_value).then(|| MetadataDelta {
+                    key: key.clone(),
+                    self_value: self_value.cloned(),
+                    other_value: other_value.cloned(),
+                })
+            })
+            .collect()
+    }
 }
@@ -1,6 +1,11 @@
 pub mod band;
+pub mod converter;
 pub mod metadata;
+pub mod mosaic;
+#[cfg(feature = "netcdf")]
+mod netcdf;
 pub mod reader;
 pub mod raster;
+pub mod read_builder;
 
 pub use super::errors::{Result, Sentinel2ArrayError};
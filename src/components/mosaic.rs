@@ -0,0 +1,112 @@
+use gdal::spatial_ref::SpatialRef;
+use nalgebra::{Matrix2, Point2};
+use ndarray::{s, Array3};
+
+use super::{
+    raster::{clip_window, Raster},
+    Result, Sentinel2ArrayError,
+};
+use crate::sensors::Sensor;
+
+/// Value of the pixels of a mosaic read no raster covers, the no data value of
+/// Sentinel-2 products.
+pub const MOSAIC_NODATA: u16 = 0;
+
+/// Tolerance, in pixels, on the alignment of the grids of a mosaic's rasters.
+const GRID_ALIGNMENT_TOLERANCE: f64 = 1e-6;
+
+/// Rasters sharing a projection, e.g. adjacent MGRS tiles of a UTM zone, read
+/// as one.
+#[derive(Debug)]
+pub struct Mosaic<S: Sensor> {
+    rasters: Vec<Raster<S>>,
+}
+
+impl<S: Sensor> Mosaic<S> {
+    /// Fails with `NoRastersInMosaic` if `rasters` is empty and with
+    /// `MosaicProjectionMismatch` if one isn't in the projection of the first.
+    pub fn new(rasters: Vec<Raster<S>>) -> Result<Self> {
+        let first = rasters.first().ok_or(Sentinel2ArrayError::NoRastersInMosaic)?;
+        let projection = SpatialRef::from_wkt(first.projection()?)?;
+        for (index, raster) in rasters.iter().enumerate().skip(1) {
+            if SpatialRef::from_wkt(raster.projection()?)? != projection {
+                return Err(Sentinel2ArrayError::MosaicProjectionMismatch(index));
+            }
+        }
+        Ok(Self { rasters })
+    }
+
+    pub fn rasters(&self) -> &[Raster<S>] {
+        &self.rasters
+    }
+
+    /// Read `band_names` over the bounding box from `min` to `max`, in the
+    /// rasters' projection, on the grid `read_bands` of the first raster uses.
+    ///
+    /// Where rasters overlap, the pixels of later ones win unless they are
+    /// `MOSAIC_NODATA`, and pixels no raster covers are `MOSAIC_NODATA`. Fails
+    /// with `MosaicGridMismatch` if the grid of a raster is offset from the
+    /// first's by a fraction of a pixel or has another resolution, and with
+    /// `BboxOutsideRaster` if the box misses every raster.
+    pub fn read_bands_bbox(
+        &self,
+        band_names: &[&str],
+        min: (f64, f64),
+        max: (f64, f64),
+    ) -> Result<Array3<u16>> {
+        let grid = self.rasters[0].highest_resolution_transform(band_names)?;
+        let inverse_grid = grid
+            .try_inverse()
+            .ok_or_else(|| Sentinel2ArrayError::BandTransformNotInvertible(band_names.join(", ")))?;
+        let min_pixel = inverse_grid.transform_point(&Point2::new(min.0, min.1));
+        let max_pixel = inverse_grid.transform_point(&Point2::new(max.0, max.1));
+        let bounds = |a: f64, b: f64| {
+            let start = a.min(b).floor();
+            (start as isize, (a.max(b).ceil() - start) as usize)
+        };
+        let (x, width) = bounds(min_pixel.x, max_pixel.x);
+        let (y, height) = bounds(min_pixel.y, max_pixel.y);
+
+        let mut mosaic = Array3::from_elem((band_names.len(), width, height), MOSAIC_NODATA);
+        let mut covered = false;
+        for (index, raster) in self.rasters.iter().enumerate() {
+            // Grid of the raster in pixels of the first one's.
+            let relative = inverse_grid * raster.highest_resolution_transform(band_names)?;
+            let shift = (relative.m13.round(), relative.m23.round());
+            if (relative.fixed_view::<2, 2>(0, 0) - Matrix2::identity()).amax()
+                > GRID_ALIGNMENT_TOLERANCE
+                || (relative.m13 - shift.0).abs() > GRID_ALIGNMENT_TOLERANCE
+                || (relative.m23 - shift.1).abs() > GRID_ALIGNMENT_TOLERANCE
+            {
+                return Err(Sentinel2ArrayError::MosaicGridMismatch(index));
+            }
+            let offset = (x - shift.0 as isize, y - shift.1 as isize);
+            let Some((read_offset, read_window)) =
+                clip_window(offset, (width, height), raster.grid_size(band_names)?)
+            else {
+                continue;
+            };
+            let tile = raster.read_bands(band_names, read_offset, read_window)?;
+            let start = (
+                (read_offset.0 - offset.0) as usize,
+                (read_offset.1 - offset.1) as usize,
+            );
+            mosaic
+                .slice_mut(s![
+                    ..,
+                    start.0..start.0 + read_window.0,
+                    start.1..start.1 + read_window.1
+                ])
+                .zip_mut_with(&tile, |value, &tile_value| {
+                    if tile_value != MOSAIC_NODATA {
+                        *value = tile_value;
+                    }
+                });
+            covered = true;
+        }
+        if !covered {
+            return Err(Sentinel2ArrayError::BboxOutsideRaster { min, max });
+        }
+        Ok(mosaic)
+    }
+}
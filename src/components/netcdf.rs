@@ -0,0 +1,184 @@
+//! Minimal writer of netCDF classic files, in the 64-bit offset format
+//! (CDF-2) every netCDF reader understands, for `Raster::write_netcdf`.
+
+use std::io::{self, Write};
+
+const MAGIC: &[u8; 4] = b"CDF\x02";
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+/// Values of a variable or an attribute, of one of the classic data types.
+pub(crate) enum Values {
+    Char(String),
+    Short(Vec<i16>),
+    Int(Vec<i32>),
+    Double(Vec<f64>),
+}
+
+impl Values {
+    fn nc_type(&self) -> u32 {
+        match self {
+            Values::Char(_) => 2,
+            Values::Short(_) => 3,
+            Values::Int(_) => 4,
+            Values::Double(_) => 6,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Values::Char(text) => text.len(),
+            Values::Short(values) => values.len(),
+            Values::Int(values) => values.len(),
+            Values::Double(values) => values.len(),
+        }
+    }
+
+    /// Size of the values in bytes, padded to 4 bytes as the format wants.
+    fn padded_size(&self) -> usize {
+        let value_size = match self {
+            Values::Char(_) => 1,
+            Values::Short(_) => 2,
+            Values::Int(_) => 4,
+            Values::Double(_) => 8,
+        };
+        (self.len() * value_size).next_multiple_of(4)
+    }
+
+    /// Write the values big endian, padded with zeros.
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let size = match self {
+            Values::Char(text) => {
+                out.write_all(text.as_bytes())?;
+                text.len()
+            }
+            Values::Short(values) => {
+                for value in values {
+                    out.write_all(&value.to_be_bytes())?;
+                }
+                values.len() * 2
+            }
+            Values::Int(values) => {
+                for value in values {
+                    out.write_all(&value.to_be_bytes())?;
+                }
+                values.len() * 4
+            }
+            Values::Double(values) => {
+                for value in values {
+                    out.write_all(&value.to_be_bytes())?;
+                }
+                values.len() * 8
+            }
+        };
+        out.write_all(&[0; 3][..self.padded_size() - size])
+    }
+}
+
+pub(crate) struct Variable {
+    pub name: String,
+    /// Indices of the variable's dimensions in `NetcdfFile::dimensions`, the
+    /// slowest varying first. None for scalars.
+    pub dimensions: Vec<usize>,
+    pub attributes: Vec<(String, Values)>,
+    pub values: Values,
+}
+
+/// netCDF file of fixed size dimensions only, no record one.
+pub(crate) struct NetcdfFile {
+    pub dimensions: Vec<(String, usize)>,
+    pub attributes: Vec<(String, Values)>,
+    pub variables: Vec<Variable>,
+}
+
+impl NetcdfFile {
+    pub(crate) fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        // Offsets take 8 bytes whatever their value, so the header's size is
+        // known before the offsets of the variables' data following it.
+        let header_size = self.header(&vec![0; self.variables.len()]).len();
+        let begins = self
+            .variables
+            .iter()
+            .scan(header_size, |begin, variable| {
+                let variable_begin = *begin;
+                *begin += variable.values.padded_size();
+                Some(variable_begin as u64)
+            })
+            .collect::<Vec<_>>();
+        out.write_all(&self.header(&begins))?;
+        for variable in &self.variables {
+            variable.values.write(out)?;
+        }
+        Ok(())
+    }
+
+    fn header(&self, begins: &[u64]) -> Vec<u8> {
+        let mut header = MAGIC.to_vec();
+        // Number of records.
+        put_u32(&mut header, 0);
+        put_list(&mut header, NC_DIMENSION, &self.dimensions, |header, (name, size)| {
+            put_name(header, name);
+            put_u32(header, *size as u32);
+        });
+        put_attributes(&mut header, &self.attributes);
+        put_list(
+            &mut header,
+            NC_VARIABLE,
+            &self.variables.iter().zip(begins).collect::<Vec<_>>(),
+            |header, (variable, begin)| {
+                put_name(header, &variable.name);
+                put_u32(header, variable.dimensions.len() as u32);
+                for &dimension in &variable.dimensions {
+                    put_u32(header, dimension as u32);
+                }
+                put_attributes(header, &variable.attributes);
+                put_u32(header, variable.values.nc_type());
+                put_u32(header, variable.values.padded_size() as u32);
+                header.extend_from_slice(&begin.to_be_bytes());
+            },
+        );
+        header
+    }
+}
+
+fn put_u32(header: &mut Vec<u8>, value: u32) {
+    header.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_name(header: &mut Vec<u8>, name: &str) {
+    // Names are written as character values are.
+    put_u32(header, name.len() as u32);
+    Values::Char(name.into())
+        .write(header)
+        .expect("writing to a vector");
+}
+
+/// Tag and number of `items` followed by them, or two zeros if there are
+/// none.
+fn put_list<T>(
+    header: &mut Vec<u8>,
+    tag: u32,
+    items: &[T],
+    mut put_item: impl FnMut(&mut Vec<u8>, &T),
+) {
+    if items.is_empty() {
+        put_u32(header, 0);
+        put_u32(header, 0);
+        return;
+    }
+    put_u32(header, tag);
+    put_u32(header, items.len() as u32);
+    for item in items {
+        put_item(header, item);
+    }
+}
+
+fn put_attributes(header: &mut Vec<u8>, attributes: &[(String, Values)]) {
+    put_list(header, NC_ATTRIBUTE, attributes, |header, (name, values)| {
+        put_name(header, name);
+        put_u32(header, values.nc_type());
+        put_u32(header, values.len() as u32);
+        values.write(header).expect("writing to a vector");
+    });
+}
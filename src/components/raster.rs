@@ -1,69 +1,3209 @@
 #![allow(dead_code)]
 
-use rasters::{prelude::{transform_window, PixelTransform}, reader::ChunkReader};
-use rayon::{iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator}, prelude::IntoParallelIterator, slice::ParallelSlice};
-use ndarray::{Array2, Array3};
-use nalgebra::Point2;
+use itertools::{Either, Itertools};
+use rasters::{
+    prelude::{transform_from_gdal, transform_window, PixelTransform, RasterWindow},
+    reader::ChunkReader,
+};
+use rayon::{
+    iter::{IntoParallelRefIterator, ParallelIterator},
+    ThreadPool,
+};
+use ndarray::{s, Array2, Array3, ArrayView2, Axis};
+use nalgebra::{Matrix2, Point2, Vector2};
+use gdal::{
+    errors::GdalError,
+    Metadata as GdalMetadata,
+    raster::{Buffer, GdalDataType, GdalType, RasterCreationOptions, ResampleAlg},
+    spatial_ref::{AxisMappingStrategy, CoordTransform, SpatialRef},
+    Dataset, DriverManager, GeoTransform,
+};
 use super::{
-    band::{BandInfo, Bands},
+    band::{BandInfo, BandStats, Bands},
+    converter::PixelConverter,
+    read_builder::ReadBuilder,
     Result,
     Sentinel2ArrayError
 };
 use crate::sensors::Sensor;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::CStr,
+    iter, mem, panic,
+    path::{Path, PathBuf},
+    ptr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::Duration,
+};
+#[cfg(feature = "arrow")]
+use arrow::{
+    array::{ArrayRef, UInt16Array, UInt32Array},
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
+#[cfg(feature = "polars")]
+use polars::{frame::DataFrame, prelude::Column};
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+#[cfg(any(feature = "mmap", feature = "netcdf"))]
+use std::{fs::File, io::{BufWriter, Write}};
+
+/// Largest chunk side of the arrays `write_zarr` writes.
+#[cfg(feature = "zarr")]
+const ZARR_CHUNK_PIXELS: usize = 512;
+
+/// GDAL's own default for the warper's working memory.
+const DEFAULT_WARP_MEM_LIMIT_MB: usize = 64;
+
+/// Rows per strip of `read_full`, a multiple of the ratio between the
+/// coarsest and finest pixel sizes so strips stay aligned for every band.
+const FULL_READ_CHUNK_ROWS: usize = 1200;
+
+/// Rows `scanlines` reads at once, aligned with the 60 m grid like
+/// `FULL_READ_CHUNK_ROWS`.
+const SCANLINE_BLOCK_ROWS: usize = 60;
+
+/// Pixels per band above which `read_bands` splits a window into tiles, see
+/// [`Raster::with_read_tile_pixels`].
+const DEFAULT_READ_TILE_PIXELS: usize = 4096 * 4096;
+
+/// Largest distance, in projection units, `self_check_georef` lets a point
+/// drift by over a round trip through WGS84.
+const GEOREF_ROUND_TRIP_TOLERANCE: f64 = 1e-3;
+
+/// Tolerance, in pixels, `validate_alignment` lets grids of bands of a
+/// resolution be apart by, and within which reads take a band to be on the
+/// read grid.
+pub(crate) const BAND_ALIGNMENT_TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hemisphere {
+    North,
+    South,
+}
+
+/// Summary of a raster in the terms of a rasterio profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub driver: String,
+    pub dtype: String,
+    pub width: usize,
+    pub height: usize,
+    pub count: usize,
+    pub crs: String,
+    /// Affine coefficients `(a, b, c, d, e, f)`, mapping `(col, row)` to
+    /// `(a * col + b * row + c, d * col + e * row + f)`.
+    pub transform: [f64; 6],
+    pub nodata: Option<f64>,
+}
+
+impl Profile {
+    /// Profile as rasterio's dict of strings, e.g. to pass along to Python.
+    pub fn to_map(&self) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("driver", self.driver.clone()),
+            ("dtype", self.dtype.clone()),
+            ("width", self.width.to_string()),
+            ("height", self.height.to_string()),
+            ("count", self.count.to_string()),
+            ("crs", self.crs.clone()),
+            ("transform", format!("{:?}", self.transform)),
+            (
+                "nodata",
+                self.nodata.map_or("None".into(), |nodata| nodata.to_string()),
+            ),
+        ])
+    }
+}
+
+/// Pixel grid in a projection, which reads can be warped onto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    /// WKT projection.
+    pub crs: String,
+    pub transform: PixelTransform,
+    pub size: (usize, usize),
+}
+
+/// How a read warped onto a [`Grid`] was produced, for provenance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformChain {
+    pub source_crs: String,
+    pub target_crs: String,
+    pub resampling: Resampling,
+    /// Geo transform of the grid the bands were read on.
+    pub source_transform: PixelTransform,
+    pub target_transform: PixelTransform,
+    /// Transform from pixels of the target grid to pixels of the source one,
+    /// `None` if the projections differ and no affine transform relates them.
+    pub composed: Option<PixelTransform>,
+}
+
+impl TransformChain {
+    /// Chain as a dict of strings, with transforms as in [`Profile::transform`].
+    pub fn to_map(&self) -> BTreeMap<&'static str, String> {
+        let coefficients = |transform| format!("{:?}", affine_coefficients(transform));
+        BTreeMap::from([
+            ("source_crs", self.source_crs.clone()),
+            ("target_crs", self.target_crs.clone()),
+            ("resampling", format!("{:?}", self.resampling)),
+            ("source_transform", coefficients(&self.source_transform)),
+            ("target_transform", coefficients(&self.target_transform)),
+            ("composed", self.composed.as_ref().map_or("None".into(), coefficients)),
+        ])
+    }
+}
+
+/// How bands coarser than the read grid get sampled onto it. Bands on the
+/// read grid itself come out as read either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Resampling {
+    /// Value of the source pixel a read pixel falls in.
+    #[default]
+    Nearest,
+    /// Weighted mean of the four closest source pixels, leaving out no data ones.
+    Bilinear,
+}
+
+/// What a read does when some of its bands fail, e.g. over an unreliable
+/// network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PartialPolicy {
+    /// Fail with the error of the first band that fails.
+    #[default]
+    FailFast,
+    /// Read all the bands that can be, and report the others.
+    BestEffort,
+}
+
+/// Output of [`Raster::read_bands_partial`].
+#[derive(Debug)]
+pub struct PartialRead {
+    /// Stack of the requested bands, in requested order.
+    pub array: Array3<u16>,
+    /// Bands that failed to read, with their error.
+    pub failed: Vec<(String, Sentinel2ArrayError)>,
+}
+
+/// What a pixel of [`Raster::read_band_diagnostic`] holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelStatus {
+    Valid(u16),
+    /// The band's GDAL no data value.
+    NoData,
+    /// Past the edges of the band.
+    OutOfBounds,
+}
+
+/// What [`Raster::read_patches`] does with the patches crossing the edges of
+/// the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgePatches {
+    /// Leave them out, every patch then lies fully within the window.
+    #[default]
+    Drop,
+    /// Keep as many as needed to cover the window, padded with zeros.
+    Pad,
+}
+
+/// Where in the pixels of the read grid coarser bands get sampled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PixelAnchor {
+    /// Pixel centres, as most GIS sample.
+    #[default]
+    Centre,
+    /// Upper left pixel corners, half a pixel off the centres. Kept for reads
+    /// matching the ones of earlier versions.
+    Corner,
+}
+
+impl PixelAnchor {
+    /// Sampling point of pixel `(x, y)`, in pixel coordinates.
+    pub(crate) fn point(self, x: usize, y: usize) -> Point2<f64> {
+        let shift = match self {
+            Self::Centre => 0.5,
+            Self::Corner => 0.,
+        };
+        Point2::new(x as f64 + shift, y as f64 + shift)
+    }
+}
+
+/// What [`Raster::read_bands`] does with requested bands it can't co-register
+/// with the finest of them by resampling alone, i.e. bands in another
+/// projection or on a rotated grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BandMismatch {
+    /// Fail with `IncompatibleBandGroups`.
+    #[default]
+    Reject,
+    /// Warp them bilinearly onto the read grid.
+    Warp,
+}
+
+/// Sensor of the metadata free rasters of [`Raster::detached`].
+#[derive(Debug)]
+pub(crate) struct Detached;
+
+impl Sensor for Detached {
+    type RasterMetadata = ();
+    type BandMetadata = ();
+
+    const GDAL_DRIVER_NAME: &'static str = "";
+}
 
 #[derive(Debug)]
 pub struct Raster<S: Sensor> {
     bands: Bands<S::BandMetadata>,
     pub metadata: S::RasterMetadata,
+    band_mismatch: BandMismatch,
+    read_tile_pixels: usize,
+}
+
+/// Clones share the band groups, and so the datasets they keep open.
+impl<S: Sensor> Clone for Raster<S>
+where
+    S::BandMetadata: Clone,
+    S::RasterMetadata: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            bands: self.bands.clone(),
+            metadata: self.metadata.clone(),
+            band_mismatch: self.band_mismatch,
+            read_tile_pixels: self.read_tile_pixels,
+        }
+    }
 }
 
 impl<S: Sensor> Raster<S> {
     pub fn new(bands: Bands<S::BandMetadata>, metadata: S::RasterMetadata) -> Self {
-        Self { bands, metadata }
+        Self {
+            bands,
+            metadata,
+            band_mismatch: BandMismatch::default(),
+            read_tile_pixels: DEFAULT_READ_TILE_PIXELS,
+        }
+    }
+
+    /// Set what `read_bands` does with bands it can't co-register.
+    pub fn with_band_mismatch(mut self, band_mismatch: BandMismatch) -> Self {
+        self.band_mismatch = band_mismatch;
+        self
+    }
+
+    /// Set the pixels per band above which `read_bands` reads a window in
+    /// strips of rows, in parallel, and reassembles them, which keeps single
+    /// GDAL requests and their decode buffers bounded. Strips are a multiple of
+    /// `SCANLINE_BLOCK_ROWS` rows, so the result is the same as a single read.
+    pub fn with_read_tile_pixels(mut self, read_tile_pixels: usize) -> Self {
+        self.read_tile_pixels = read_tile_pixels;
+        self
     }
 
-    fn get_band_info(&self, band_name: &'static str) -> Result<&BandInfo<S::BandMetadata>> {
+    fn get_band_info(&self, band_name: &str) -> Result<&BandInfo<S::BandMetadata>> {
         self.bands.get(band_name)
     }
 
-    fn read_band(&self,
-        band_info: &BandInfo<S::BandMetadata>,
-        off: (isize, isize),
-        size: (usize, usize),
-    ) -> Result<Array2<u16>> {
-       band_info.reader().read_as_array(off, size).map_err(Sentinel2ArrayError::RastersError)
+    /// Close the datasets kept open between reads. Reads reuse one open
+    /// dataset per band group and thread reading it at once, reopening them
+    /// as needed after this.
+    pub fn clear_cache(&self) {
+        self.bands.clear_cache()
+    }
+
+    pub(crate) fn datasets_opened(&self) -> usize {
+        self.bands.datasets_opened()
+    }
+
+    pub(crate) fn band_infos(&self) -> &Bands<S::BandMetadata> {
+        &self.bands
+    }
+
+    pub fn band_names(&self) -> Vec<&String> {
+        self.bands.names()
+    }
+
+    /// Band names with their resolution, see [`BandInfo::resolution`], in the
+    /// order of `band_names`.
+    pub fn bands(&self) -> impl Iterator<Item = (&str, u8)> {
+        self.bands
+            .iter_sorted()
+            .map(|(band_name, band_info)| (band_name.as_str(), band_info.resolution()))
+    }
+
+    /// Number of band groups, i.e. GDAL (sub)datasets, the bands are read from.
+    pub fn group_count(&self) -> usize {
+        self.bands.group_count()
+    }
+
+    /// GDAL `(scale, offset)` of `band_name`, see [`BandInfo::calibration`].
+    pub fn band_calibration(&self, band_name: &str) -> Result<Option<(f64, f64)>> {
+        self.get_band_info(band_name).map(BandInfo::calibration)
+    }
+
+    /// Pixel size of `band_name` in meters, see [`BandInfo::resolution`].
+    pub fn band_resolution(&self, band_name: &str) -> Result<u8> {
+        self.get_band_info(band_name).map(BandInfo::resolution)
+    }
+
+    /// Pixel size of `band_name` in projection units, straight from its geo
+    /// transform, unlike the truncated [`BandInfo::resolution`].
+    pub fn band_resolution_precise(&self, band_name: &str) -> Result<f64> {
+        self.get_band_info(band_name)
+            .map(|band_info| band_info.geo_transform().m11.abs())
+    }
+
+    /// GDAL block size of `band_name`, see [`BandInfo::block_size`].
+    pub fn band_block_size(&self, band_name: &str) -> Result<(usize, usize)> {
+        self.get_band_info(band_name)?.block_size()
+    }
+
+    /// GDAL data type of `band_name`, see [`BandInfo::data_type`].
+    pub fn band_dtype(&self, band_name: &str) -> Result<GdalDataType> {
+        self.get_band_info(band_name).map(BandInfo::data_type)
+    }
+
+    /// GDAL name of the dataset of `band_name`, e.g. a subdataset of the
+    /// product, and the 1 based index of the band in it, to open it with GDAL
+    /// directly. Open options the raster was opened with have to be passed
+    /// along too, see `Sentinel2::raster_from_with_gdal_options`.
+    pub fn band_source(&self, band_name: &str) -> Result<(PathBuf, usize)> {
+        let band_info = self.get_band_info(band_name)?;
+        Ok((PathBuf::from(band_info.gdal_dataset_path()), band_info.index()))
+    }
+
+    /// GDAL no data value of `band_name`, if it has one.
+    pub fn band_no_data_value(&self, band_name: &str) -> Result<Option<f64>> {
+        self.get_band_info(band_name).map(BandInfo::no_data_value)
+    }
+
+    pub fn band_metadata(&self, band_name: &str) -> Result<&S::BandMetadata> {
+        self.get_band_info(band_name).map(BandInfo::metadata)
+    }
+
+    /// WKT projection shared by the bands.
+    pub(crate) fn projection(&self) -> Result<&str> {
+        self.bands
+            .iter()
+            .next()
+            .map(|(_, band_info)| band_info.crs())
+            .ok_or(Sentinel2ArrayError::NoBandsFound)
+    }
+
+    /// Projection shared by the bands, as a GDAL spatial reference.
+    pub fn crs(&self) -> Result<SpatialRef> {
+        Ok(SpatialRef::from_wkt(self.projection()?)?)
+    }
+
+    /// EPSG code of the raster's projection, e.g. 32633 for UTM zone 33N.
+    /// Fails with `NoEpsgCode` if GDAL can't identify one.
+    pub fn epsg(&self) -> Result<u32> {
+        let crs = self.crs()?;
+        match (crs.auth_name(), crs.auth_code()) {
+            (Ok(name), Ok(code)) if name.eq_ignore_ascii_case("EPSG") => Ok(code as u32),
+            _ => Err(Sentinel2ArrayError::NoEpsgCode(self.projection()?.into())),
+        }
+    }
+
+    /// UTM zone number and hemisphere of the raster's projection.
+    pub fn utm_zone(&self) -> Result<(u8, Hemisphere)> {
+        let projection = self.projection()?;
+        let spatial_ref = SpatialRef::from_wkt(projection)?;
+        match spatial_ref.auth_code() {
+            Ok(code @ 32601..=32660) => Some(((code - 32600) as u8, Hemisphere::North)),
+            Ok(code @ 32701..=32760) => Some(((code - 32700) as u8, Hemisphere::South)),
+            _ => utm_zone_from_name(&spatial_ref.name()?),
+        }
+        .ok_or(Sentinel2ArrayError::NotUtmProjection(projection.into()))
+    }
+
+    /// Band infos of `band_names`, failing with `NoBandsFound` if there are
+    /// none, which the grid of a read needs.
+    fn get_bands_info(&self, band_names: &[&str]) -> Result<Vec<&BandInfo<S::BandMetadata>>> {
+        if band_names.is_empty() {
+            return Err(Sentinel2ArrayError::NoBandsFound);
+        }
+        band_names
+            .iter()
+            .map(|band_name| self.get_band_info(band_name))
+            .collect()
+    }
+
+    /// Size of the grid `read_bands` resamples `band_names` onto.
+    pub(crate) fn grid_size(&self, band_names: &[&str]) -> Result<(usize, usize)> {
+        self.get_bands_info(band_names)
+            .map(|bands_info| grid_size(&bands_info))
+    }
+
+    /// Geo transform of the grid `read_bands` resamples `band_names` onto.
+    pub fn highest_resolution_transform(&self, band_names: &[&str]) -> Result<PixelTransform> {
+        self.get_bands_info(band_names)
+            .map(|bands_info| highest_resolution_transform(&bands_info))
+    }
+
+    /// Band of the finest pixel size, whose grid covers the raster's extent.
+    fn highest_resolution_band(&self) -> Result<&BandInfo<S::BandMetadata>> {
+        self.bands
+            .iter()
+            .map(|(_, band_info)| band_info)
+            .min_by(|a, b| a.grid_order(b))
+            .ok_or(Sentinel2ArrayError::NoBandsFound)
+    }
+
+    /// Size of the raster at its highest resolution.
+    pub fn size(&self) -> Result<(usize, usize)> {
+        self.highest_resolution_band().map(BandInfo::raster_size)
+    }
+
+    /// Distinct resolutions of the bands, see [`BandInfo::resolution`], finest
+    /// first.
+    pub fn resolutions(&self) -> Vec<u8> {
+        self.bands
+            .iter()
+            .map(|(_, band_info)| band_info.resolution())
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Names of the bands at `resolution`, in the order of `band_names`.
+    pub fn bands_at(&self, resolution: u8) -> Vec<&str> {
+        self.band_names()
+            .into_iter()
+            .filter(|band_name| {
+                self.get_band_info(band_name)
+                    .is_ok_and(|band_info| band_info.resolution() == resolution)
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// rasterio like profile of the raster at its highest resolution.
+    pub fn profile(&self) -> Result<Profile> {
+        let band_info = self.highest_resolution_band()?;
+        let (width, height) = band_info.raster_size();
+        let transform = band_info.geo_transform();
+        Ok(Profile {
+            driver: S::GDAL_DRIVER_NAME.into(),
+            dtype: "uint16".into(),
+            width,
+            height,
+            count: self.band_names().len(),
+            crs: band_info.crs().into(),
+            transform: affine_coefficients(&transform),
+            nodata: band_info.no_data_value(),
+        })
+    }
+
+    /// Grid of the finest of `band_names`, which they get read onto.
+    pub(crate) fn bands_grid(&self, band_names: &[&str]) -> Result<Grid> {
+        let bands_info = self.get_bands_info(band_names)?;
+        let band_info = finest_band(&bands_info).ok_or(Sentinel2ArrayError::NoBandsFound)?;
+        Ok(Grid {
+            crs: band_info.crs().into(),
+            transform: band_info.geo_transform(),
+            size: band_info.raster_size(),
+        })
+    }
+
+    /// Grid of the raster at its highest resolution.
+    pub fn grid(&self) -> Result<Grid> {
+        let band_info = self.highest_resolution_band()?;
+        Ok(Grid {
+            crs: band_info.crs().into(),
+            transform: band_info.geo_transform(),
+            size: band_info.raster_size(),
+        })
+    }
+
+    /// Content of an ESRI world file (`.tfw`, `.jgw`, ...) georeferencing a
+    /// read at `offset` of the highest resolution grid: pixel sizes and
+    /// rotations, then the coordinates of the centre of the upper left pixel.
+    pub fn world_file_string(&self, offset: (isize, isize)) -> Result<String> {
+        let transform = window_transform(self.highest_resolution_band()?.geo_transform(), offset);
+        let centre = transform.transform_point(&Point2::new(0.5, 0.5));
+        Ok([
+            transform.m11,
+            transform.m21,
+            transform.m12,
+            transform.m22,
+            centre.x,
+            centre.y,
+        ]
+        .map(|value| format!("{value}\n"))
+        .concat())
+    }
+
+    /// Number of bands at every distinct pixel size, in meters, finest first.
+    pub fn transform_report(&self) -> Vec<(u8, usize)> {
+        self.bands
+            .iter()
+            .map(|(_, band_info)| band_info.resolution())
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect()
+    }
+
+    /// Native geo transform of `band_name`, as GDAL's 6 coefficients.
+    pub fn band_geo_transform(&self, band_name: &str) -> Result<GeoTransform> {
+        self.get_band_info(band_name)
+            .map(|band_info| transform_to_gdal(&band_info.geo_transform()))
+    }
+
+    /// Size in bytes of the array `read_bands` allocates for `band_names` over `window`.
+    pub fn estimate_read_bytes(&self, band_names: &[&str], window: (usize, usize)) -> usize {
+        band_names.len() * window.0 * window.1 * mem::size_of::<u16>()
+    }
+
+    pub fn read(&self) -> ReadBuilder<'_, S> {
+        ReadBuilder::new(self)
+    }
+
+    /// Read `band_names`, in that order, over `window` of their highest
+    /// resolution grid, resampling the coarser bands onto it. Fails with
+    /// `WindowOutOfBounds` if the window isn't within the grid, see
+    /// `read_bands_clamped` to read what's left of it instead.
+    pub fn read_bands<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<u16>> {
+        self.read_bands_cast(band_names, offset, window)
     }
 
-    pub fn read_bands(
+    /// Like `read_bands`, converting pixels to `T` as GDAL does, e.g. to
+    /// `f32` or `u32`. Fails with `DataTypeMismatch` if a band's data type
+    /// doesn't convert to `T` without loss, e.g. `u16` ones to `u8` or `i16`.
+    pub fn read_bands_cast<T: GdalType + Copy + Send, N: AsRef<str>>(
         &self,
-        band_names: Vec<&'static str>,
+        band_names: impl IntoIterator<Item = N>,
         offset: (isize, isize),
         window: (usize, usize),
+    ) -> Result<Array3<T>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        for (band_name, band_info) in band_names.iter().zip(&bands_info) {
+            if band_info.data_type().is_conversion_lossy(T::datatype()) {
+                return Err(Sentinel2ArrayError::DataTypeMismatch {
+                    band_name: band_name.to_string(),
+                    data_type: band_info.data_type().name(),
+                    requested: T::datatype().name(),
+                });
+            }
+        }
+        check_within(offset, window, grid_size(&bands_info))?;
+        self.read_bands_unchecked(&band_names, &bands_info, offset, window)
+    }
+
+    /// Like `read_bands` over each of `windows`, e.g. chips for a model, in
+    /// their order. The bands get looked up and checked once, and the windows
+    /// read in parallel on datasets the band groups keep open between reads.
+    ///
+    /// Fails fast: the first window out of bounds or failed read fails the
+    /// whole batch, as `read_bands` would have for that window. Windows are
+    /// each read in one go, not in strips as `with_read_tile_pixels` sets.
+    pub fn read_bands_batch<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        windows: &[RasterWindow],
+    ) -> Result<Vec<Array3<u16>>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let raster_size = grid_size(&bands_info);
+        for &(offset, window) in windows {
+            check_within(offset, window, raster_size)?;
+        }
+        self.check_band_mismatch(&band_names, &bands_info)?;
+        windows
+            .par_iter()
+            .map(|&(offset, window)| read_bands_info(&bands_info, offset, window))
+            .collect()
+    }
+
+    /// Like `read_bands`, calling `progress(completed_bands, total_bands)` as
+    /// each band gets read, e.g. to report on slow `/vsicurl/` reads.
+    ///
+    /// Bands are read in parallel and `progress` gets called from the thread
+    /// that read the band, so calls may overlap and arrive out of order: a
+    /// call with 3 completed bands may come before the one with 2. Every count
+    /// from 1 to the number of bands is reported exactly once. The window is
+    /// read in one go, not in strips as `with_read_tile_pixels` sets.
+    pub fn read_bands_with_progress<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        progress: impl Fn(usize, usize) + Sync,
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        self.check_band_mismatch(&band_names, &bands_info)?;
+        read_bands_info_with_progress(&bands_info, offset, window, &progress)
+    }
+
+    /// Like `read_bands`, but clipping the window to the grid rather than
+    /// failing, together with the window that got read. Still fails with
+    /// `WindowOutOfBounds` if nothing of the window is left.
+    pub fn read_bands_clamped<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array3<u16>, RasterWindow)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let raster_size = grid_size(&bands_info);
+        let (offset, window) = clip_window(offset, window, raster_size).ok_or(
+            Sentinel2ArrayError::WindowOutOfBounds {
+                offset,
+                window,
+                raster_size,
+            },
+        )?;
+        self.read_bands_unchecked(&band_names, &bands_info, offset, window)
+            .map(|array| (array, (offset, window)))
+    }
+
+    /// Like `read_bands`, but onto the grid of the raster's bands at
+    /// `resolution` meters, e.g. 20 to read B2 and B11 together at 20 m and
+    /// save memory. `offset` and `window` are in pixels of that grid. Fails with
+    /// `NoBandsAtResolution` if no band of the raster is at `resolution`.
+    ///
+    /// Every band gets nearest sampled at the centres of the grid's pixels:
+    /// finer bands aren't averaged down, so a no data pixel of theirs comes
+    /// back as is and valid pixels next to it don't get mixed with it.
+    pub fn read_bands_at<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        resolution: u8,
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let reference = self
+            .bands
+            .iter()
+            .map(|(_, band_info)| band_info)
+            .filter(|band_info| band_info.resolution() == resolution)
+            .min_by(|a, b| a.grid_order(b))
+            .ok_or(Sentinel2ArrayError::NoBandsAtResolution(resolution))?;
+        check_within(offset, window, reference.raster_size())?;
+        self.check_band_mismatch(&band_names, &bands_info)?;
+        read_bands_info_on_grid(
+            &bands_info,
+            reference.geo_transform(),
+            offset,
+            window,
+            Resampling::Nearest,
+            PixelAnchor::default(),
+        )
+    }
+
+    /// Like `read_bands_cast`, but with the pixels of the window past the
+    /// bands filled in from their nearest edge pixel.
+    fn read_bands_unchecked<T: GdalType + Copy + Send>(
+        &self,
+        band_names: &[&str],
+        bands_info: &[&BandInfo<S::BandMetadata>],
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<T>> {
+        self.check_band_mismatch(band_names, bands_info)?;
+        if window.0 * window.1 <= self.read_tile_pixels {
+            return read_bands_info(bands_info, offset, window);
+        }
+        let tile_rows = (self.read_tile_pixels / window.0 / SCANLINE_BLOCK_ROWS).max(1)
+            * SCANLINE_BLOCK_ROWS;
+        // Strips get read in parallel, each band of them on a dataset of its
+        // own, see `BandGroup::datasets`.
+        let tiles = (0..window.1)
+            .step_by(tile_rows)
+            .collect::<Vec<_>>()
+            .par_iter()
+            .map(|&row| {
+                let rows = tile_rows.min(window.1 - row);
+                read_bands_info(bands_info, (offset.0, offset.1 + row as isize), (window.0, rows))
+            })
+            .collect::<Result<Vec<Array3<T>>>>()?;
+        let tiles = tiles.iter().map(Array3::view).collect::<Vec<_>>();
+        Ok(ndarray::concatenate(Axis(2), &tiles).unwrap())
+    }
+
+    /// Fail with `IncompatibleBandGroups` listing the bands of `bands_info`
+    /// not in the projection of the finest one or on a rotated grid, unless
+    /// they get warped.
+    fn check_band_mismatch(
+        &self,
+        band_names: &[&str],
+        bands_info: &[&BandInfo<S::BandMetadata>],
+    ) -> Result<()> {
+        if self.band_mismatch == BandMismatch::Warp || bands_info.is_empty() {
+            return Ok(());
+        }
+        let grid_crs = grid_crs(bands_info, highest_resolution_transform(bands_info));
+        let canonical = SpatialRef::from_wkt(grid_crs)?;
+        let mut incompatible = Vec::new();
+        for (band_name, band_info) in band_names.iter().zip(bands_info) {
+            let transform = band_info.geo_transform();
+            let rotated = transform.m12 != 0. || transform.m21 != 0.;
+            if rotated
+                || band_info.crs() != grid_crs
+                    && SpatialRef::from_wkt(band_info.crs())? != canonical
+            {
+                incompatible.push(band_name.to_string());
+            }
+        }
+        if incompatible.is_empty() {
+            Ok(())
+        } else {
+            Err(Sentinel2ArrayError::IncompatibleBandGroups(incompatible))
+        }
+    }
+
+    /// Like `read_bands`, but with the bands read in parallel on `pool` rather
+    /// than on rayon's global pool, e.g. a single threaded one to keep reads
+    /// from competing with other work. Constructing a raster reads its
+    /// metadata sequentially, so it doesn't need a pool.
+    pub fn read_bands_in_pool<N: AsRef<str> + Send>(
+        &self,
+        band_names: impl IntoIterator<Item = N> + Send,
+        offset: (isize, isize),
+        window: (usize, usize),
+        pool: &ThreadPool,
     ) -> Result<Array3<u16>> {
-        let bands_info = band_names.iter().map(|band_name| self.get_band_info(band_name)).collect::<Result<Vec<&BandInfo<S::BandMetadata>>>>()?;
-        let highest_resolution_transform = bands_info.iter().map(|band_info| band_info.geo_transform()).reduce(|prev, next| if next.m11 < prev.m11 {next} else {prev}).unwrap();
-        let band_rasters = bands_info.into_par_iter().map(
-            |band_info| {
-                let transform = band_info
-                    .geo_transform()
-                    .try_inverse().unwrap()
-                    * highest_resolution_transform;
-                    let (cor_off, cor_size) = transform_window(
-                        (offset, window),
-                        transform,
-                        band_info.raster_size(),
-                    );
-                   self.read_band(band_info, cor_off, cor_size).map(|array| (array, transform))
+        pool.install(|| self.read_bands(band_names, offset, window))
+    }
+
+    /// Like `read_bands`, for a single band.
+    pub fn read_band(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array2<u16>> {
+        self.read_bands([band_name], offset, window)
+            .map(|array| array.index_axis_move(Axis(0), 0))
+    }
+
+    /// Statistics of `band_name` over `window`, leaving out pixels holding the
+    /// band's GDAL no data value. The standard deviation is the population
+    /// one. Fails with `NoValidData` if no pixel is left.
+    pub fn band_stats(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<BandStats> {
+        let no_data_value = self.band_no_data_value(band_name)?.map(|value| value as u16);
+        let band = self.read_band(band_name, offset, window)?;
+        let stats = band
+            .par_iter()
+            .filter(|&&value| Some(value) != no_data_value)
+            .fold(RunningStats::default, |stats, &value| stats.push(value.into()))
+            .reduce(RunningStats::default, RunningStats::merge);
+        if stats.count == 0 {
+            return Err(Sentinel2ArrayError::NoValidData(band_name.into()));
+        }
+        Ok(BandStats {
+            min: stats.min,
+            max: stats.max,
+            mean: stats.mean,
+            std_dev: (stats.m2 / stats.count as f64).sqrt(),
+        })
+    }
+
+    /// Counts of the values of `band_name` over `window` in `bins` equal
+    /// width buckets from `range.0` to `range.1`, both included, or over the
+    /// window's values if `range` is `None`. Pixels holding the band's GDAL no
+    /// data value and values out of `range` are left out. Fails with
+    /// `NoValidData` if there's no `range` and no pixel is left. Panics if
+    /// `bins` is zero.
+    pub fn band_histogram(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+        bins: usize,
+        range: Option<(u16, u16)>,
+    ) -> Result<Vec<u64>> {
+        assert!(bins > 0, "histogram of zero bins");
+        let no_data_value = self.band_no_data_value(band_name)?.map(|value| value as u16);
+        let band = self.read_band(band_name, offset, window)?;
+        let valid = || band.par_iter().filter(|&&value| Some(value) != no_data_value);
+        let (min, max) = match range {
+            Some(range) => range,
+            None => valid()
+                .fold(|| (u16::MAX, u16::MIN), |(min, max), &value| {
+                    (min.min(value), max.max(value))
+                })
+                .reduce_with(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)))
+                .filter(|(min, max)| min <= max)
+                .ok_or_else(|| Sentinel2ArrayError::NoValidData(band_name.into()))?,
+        };
+        let width = (f64::from(max) - f64::from(min) + 1.) / bins as f64;
+        Ok(valid()
+            .filter(|&&value| (min..=max).contains(&value))
+            .fold(
+                || vec![0u64; bins],
+                |mut counts, &value| {
+                    let bin = ((f64::from(value - min) / width) as usize).min(bins - 1);
+                    counts[bin] += 1;
+                    counts
+                },
+            )
+            .reduce(
+                || vec![0u64; bins],
+                |mut counts, partial| {
+                    counts.iter_mut().zip(partial).for_each(|(count, partial)| *count += partial);
+                    counts
+                },
+            ))
+    }
+
+    /// 64 bit FNV-1a hash of the size and little endian, row-major values of
+    /// `band_name` over `window`. It only depends on the read values, so equal
+    /// windows of different products or runs hash equal, e.g. to deduplicate
+    /// all no data tiles. Not meant to resist deliberate collisions.
+    pub fn band_content_hash(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<u64> {
+        let band = self.read_band(band_name, offset, window)?;
+        let size = [window.0 as u64, window.1 as u64].into_iter().flat_map(u64::to_le_bytes);
+        let values = band.t().iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>();
+        Ok(fnv1a(size.chain(values)))
+    }
+
+    /// `band_name` over `window` of its own grid, telling valid pixels apart
+    /// from no data and from pixels outside of the band, which `read_band`
+    /// fills in from its edges. Meant for checking reads rather than for
+    /// performance.
+    pub fn read_band_diagnostic(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array2<PixelStatus>> {
+        let band_info = self.get_band_info(band_name)?;
+        let (width, height) = band_info.raster_size();
+        let no_data_value = band_info.no_data_value().map(|value| value as u16);
+        let band = self
+            .read_bands_unchecked::<u16>(&[band_name], &[band_info], offset, window)?
+            .index_axis_move(Axis(0), 0);
+        Ok(Array2::from_shape_fn(window, |(x, y)| {
+            let (band_x, band_y) = (offset.0 + x as isize, offset.1 + y as isize);
+            if !(0..width as isize).contains(&band_x) || !(0..height as isize).contains(&band_y) {
+                PixelStatus::OutOfBounds
+            } else if Some(band[[x, y]]) == no_data_value {
+                PixelStatus::NoData
+            } else {
+                PixelStatus::Valid(band[[x, y]])
             }
-        ).collect::<Result<Vec<(Array2<u16>, PixelTransform)>>>()?;
+        }))
+    }
+
+    /// Like `read_bands`, for every band of the raster.
+    ///
+    /// Channels along the first axis follow the returned band names, which are
+    /// sorted as in [`Raster::band_names`].
+    pub fn read_all_bands(
+        &self,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Vec<String>, Array3<u16>)> {
+        let band_names = self.band_names().into_iter().cloned().collect::<Vec<String>>();
+        let array = self.read_bands(&band_names, offset, window)?;
+        Ok((band_names, array))
+    }
+
+    /// Like `read_bands`, but keeping every resolution group on its native grid.
+    ///
+    /// `offset` and `window` are on the highest resolution grid of
+    /// `band_names` and get scaled to each group's grid, widened to whole
+    /// pixels. Arrays are keyed by pixel size and stack the group's bands in
+    /// requested order.
+    pub fn read_grouped<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<HashMap<u8, Array3<u16>>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        let highest_resolution = highest_resolution_transform(&bands_info).m11;
+        bands_info
+            .into_iter()
+            .into_group_map_by(|band_info| band_info.resolution())
+            .into_iter()
+            .map(|(resolution, group)| {
+                let scale = highest_resolution / group[0].geo_transform().m11;
+                let scaled = |offset: isize, window: usize| {
+                    let start = (offset as f64 * scale).floor();
+                    let end = ((offset as f64 + window as f64) * scale).ceil();
+                    (start as isize, (end - start) as usize)
+                };
+                let (x, width) = scaled(offset.0, window.0);
+                let (y, height) = scaled(offset.1, window.1);
+                read_bands_info(&group, (x, y), (width, height)).map(|array| (resolution, array))
+            })
+            .collect()
+    }
+
+    /// Like `read_bands`, with each band keyed by name rather than stacked,
+    /// all on the highest resolution grid.
+    pub fn read_bands_map<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<HashMap<String, Array2<u16>>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let array = self.read_bands(&band_names, offset, window)?;
+        Ok(band_names
+            .into_iter()
+            .zip(array.outer_iter())
+            .map(|(band_name, band)| (band_name.to_string(), band.to_owned()))
+            .collect())
+    }
+
+    /// Like `read_grouped`, keyed by band name. Arrays are each on their
+    /// band's native grid, so their shapes differ between resolutions.
+    pub fn read_bands_native<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<HashMap<String, Array2<u16>>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let resolutions = band_names
+            .iter()
+            .map(|band_name| self.get_band_info(band_name).map(BandInfo::resolution))
+            .collect::<Result<Vec<_>>>()?;
+        let grouped = self.read_grouped(band_names.clone(), offset, window)?;
+        let mut group_indices = HashMap::<u8, usize>::new();
+        Ok(band_names
+            .into_iter()
+            .zip(resolutions)
+            .map(|(band_name, resolution)| {
+                let index = group_indices.entry(resolution).or_default();
+                let array = grouped[&resolution].index_axis(Axis(0), *index).to_owned();
+                *index += 1;
+                (band_name.to_string(), array)
+            })
+            .collect())
+    }
+
+    /// Like `read_bands`, together with the geo transform mapping pixel
+    /// coordinates of the window to map coordinates in the raster's
+    /// projection.
+    pub fn read_bands_georef<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array3<u16>, PixelTransform)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let transform =
+            window_transform(self.highest_resolution_transform(&band_names)?, offset);
+        self.read_bands(band_names, offset, window).map(|array| (array, transform))
+    }
 
+    /// Like `read_bands`, together with the map coordinates, in the raster's
+    /// projection, of the pixel centres along the `x` and `y` axes of the
+    /// window. Assumes a north up grid, as Sentinel-2 ones are.
+    pub fn read_bands_coords<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array3<u16>, Vec<f64>, Vec<f64>)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let transform =
+            window_transform(self.highest_resolution_transform(&band_names)?, offset);
+        let xs = (0..window.0)
+            .map(|x| transform.transform_point(&Point2::new(x as f64 + 0.5, 0.5)).x)
+            .collect();
+        let ys = (0..window.1)
+            .map(|y| transform.transform_point(&Point2::new(0.5, y as f64 + 0.5)).y)
+            .collect();
+        self.read_bands(band_names, offset, window)
+            .map(|array| (array, xs, ys))
+    }
+
+    /// Like `read_bands`, but with GDAL reading every band straight into the
+    /// output window with its own nearest neighbour resampling, instead of
+    /// sampling it pixel by pixel.
+    ///
+    /// GDAL stretches the whole band pixels covering the window over it, so
+    /// the output matches `read_bands` as long as the window's edges fall on
+    /// pixel edges of every band, e.g. even offsets and sizes for 20 m bands
+    /// on the 10 m grid. Otherwise the coarse pixels get shifted by a fraction
+    /// of a pixel, increasingly towards the right and bottom of the window.
+    pub fn read_bands_gdal<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        let grid = highest_resolution_transform(&bands_info);
+        let band_data = bands_info
+            .par_iter()
+            .map(|band_info| {
+                let transform = inverse_band_transform(band_info)? * grid;
+                let (cor_off, cor_size) =
+                    transform_window((offset, window), transform, band_info.raster_size());
+                let reader = band_info.open_reader()?;
+                let buffer = reader.0.rasterband(reader.1)?.read_as::<u16>(
+                    cor_off,
+                    cor_size,
+                    window,
+                    Some(ResampleAlg::NearestNeighbour),
+                )?;
+                Ok(buffer.into_shape_and_vec().1)
+            })
+            .collect::<Result<Vec<Vec<u16>>>>()?;
+        // GDAL buffers are row major.
         Ok(Array3::from_shape_fn(
-            (band_rasters.len(), window.0, window.1),
-            |(c, x, y)| {
-                let (band_raster, transform) = &band_rasters[c];
-                let corrected_coords = transform.transform_point(&Point2::new(x as f64, y as f64));
-                band_raster[[corrected_coords.x as usize, corrected_coords.y as usize]]
-            },
+            (band_data.len(), window.0, window.1),
+            |(c, x, y)| band_data[c][y * window.0 + x],
         ))
     }
+
+    /// Like `read_bands`, but sampling coarser bands with `resampling`.
+    pub fn read_bands_resampled<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        resampling: Resampling,
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.read_bands_anchored(&band_names, offset, window, resampling, PixelAnchor::default())
+    }
+
+    /// Like `read_bands_resampled`, but sampling at `anchor` of the pixels.
+    pub(crate) fn read_bands_anchored(
+        &self,
+        band_names: &[&str],
+        offset: (isize, isize),
+        window: (usize, usize),
+        resampling: Resampling,
+        anchor: PixelAnchor,
+    ) -> Result<Array3<u16>> {
+        let bands_info = self.get_bands_info(band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        self.read_bands_anchored_unchecked(band_names, offset, window, resampling, anchor)
+    }
+
+    /// Like `read_bands_anchored`, but with the pixels of the window past the
+    /// bands filled in from their nearest edge pixel.
+    pub(crate) fn read_bands_anchored_unchecked(
+        &self,
+        band_names: &[&str],
+        offset: (isize, isize),
+        window: (usize, usize),
+        resampling: Resampling,
+        anchor: PixelAnchor,
+    ) -> Result<Array3<u16>> {
+        let bands_info = self.get_bands_info(band_names)?;
+        self.check_band_mismatch(band_names, &bands_info)?;
+        let grid = highest_resolution_transform(&bands_info);
+        read_bands_info_on_grid(&bands_info, grid, offset, window, resampling, anchor)
+    }
+
+    /// Build overviews of every band at the decimation factors `levels`, e.g.
+    /// `[2, 4, 8, 16]`, for `read_thumbnail` and `read_band_overview` to read
+    /// rather than the full resolution bands. They get written into the bands'
+    /// datasets, which fails with `OverviewsNotWritable` unless GDAL can open
+    /// them for update: a product in a zip archive needs extracting first.
+    pub fn build_overviews(&self, levels: &[u32], resampling: Resampling) -> Result<()> {
+        let resampling = match resampling {
+            Resampling::Nearest => "NEAREST",
+            Resampling::Bilinear => "BILINEAR",
+        };
+        self.bands.build_overviews(levels, resampling)
+    }
+
+    /// Number of overviews of `band_name`, see [`Raster::build_overviews`].
+    pub fn band_overview_count(&self, band_name: &str) -> Result<usize> {
+        self.get_band_info(band_name)?.overview_count()
+    }
+
+    /// Whole `band_name` averaged down to fit within `max_dim` pixels, keeping
+    /// its aspect ratio. It's read from the coarsest overview still at least
+    /// `max_dim` pixels large, or from the band itself if there's none.
+    pub fn read_band_overview(&self, band_name: &str, max_dim: usize) -> Result<Array2<u16>> {
+        let shape = fit_within(self.get_band_info(band_name)?.raster_size(), max_dim);
+        self.read_band_decimated(band_name, shape, ResampleAlg::Average)
+    }
+
+    /// Read `band_names` over the whole extent decimated to fit within
+    /// `max_dim` pixels, keeping the aspect ratio of the highest resolution
+    /// grid. Every band is read with nearest neighbour resampling from its
+    /// coarsest overview still at least as large as the output, or from the
+    /// band itself if there's none, onto the same shape.
+    pub fn read_thumbnail<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        max_dim: usize,
+    ) -> Result<Array3<u16>> {
+        let shape = fit_within(self.size()?, max_dim);
+        let bands = band_names
+            .into_iter()
+            .map(|band_name| {
+                self.read_band_decimated(band_name.as_ref(), shape, ResampleAlg::NearestNeighbour)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Array3::from_shape_fn((bands.len(), shape.0, shape.1), |(c, x, y)| {
+            bands[c][[x, y]]
+        }))
+    }
+
+    /// Whole `band_name` resampled onto `shape` with `resample_alg`, from the
+    /// coarsest overview at least as large as `shape`.
+    fn read_band_decimated(
+        &self,
+        band_name: &str,
+        shape: (usize, usize),
+        resample_alg: ResampleAlg,
+    ) -> Result<Array2<u16>> {
+        let reader = self.get_band_info(band_name)?.open_reader()?;
+        let band = reader.0.rasterband(reader.1)?;
+        let max_dim = shape.0.max(shape.1);
+        let overviews = (0..band.overview_count()? as usize)
+            .map(|index| band.overview(index))
+            .collect::<gdal::errors::Result<Vec<_>>>()?;
+        let source = overviews
+            .iter()
+            .filter(|overview| overview.x_size().max(overview.y_size()) >= max_dim)
+            .min_by_key(|overview| overview.x_size())
+            .unwrap_or(&band);
+        let buffer =
+            source.read_as::<u16>((0, 0), source.size(), shape, Some(resample_alg))?;
+        let ((width, _), data) = buffer.into_shape_and_vec();
+        // GDAL buffers are row major.
+        Ok(Array2::from_shape_fn(shape, |(x, y)| data[y * width + x]))
+    }
+
+    /// Read a window of `band_name` as is, with `src_offset` and `src_window`
+    /// in the band's native pixel space rather than on a read grid.
+    pub fn read_band_source_window(
+        &self,
+        band_name: &str,
+        src_offset: (isize, isize),
+        src_window: (usize, usize),
+    ) -> Result<Array2<u16>> {
+        read_band(self.get_band_info(band_name)?, src_offset, src_window)
+    }
+
+    /// Smallest window of `band_name`'s own grid holding all of its valid,
+    /// i.e. not no data, pixels. Bands without a no data value are taken to
+    /// mark missing data with 0, as Sentinel-2 does outside of the swath.
+    pub fn valid_extent(&self, band_name: &str) -> Result<RasterWindow> {
+        let band_info = self.get_band_info(band_name)?;
+        let no_data_value = band_info.no_data_value().map_or(0, |value| value as u16);
+        let array = read_band(band_info, (0, 0), band_info.raster_size())?;
+        valid_window(array.view(), no_data_value)
+            .ok_or_else(|| Sentinel2ArrayError::NoValidData(band_name.into()))
+    }
+
+    /// Check that the highest resolution grid and its projection agree, by
+    /// taking the map coordinates of the centre of the grid to WGS84 and back.
+    /// Fails with `GeoReferenceInconsistent` if they drift by more than
+    /// `GEOREF_ROUND_TRIP_TOLERANCE` or can't be transformed at all.
+    pub fn self_check_georef(&self) -> Result<()> {
+        let grid = self.grid()?;
+        let centre = grid
+            .transform
+            .transform_point(&Point2::new(grid.size.0 as f64 / 2., grid.size.1 as f64 / 2.));
+        let mut projected = SpatialRef::from_wkt(&grid.crs)?;
+        let mut wgs84 = SpatialRef::from_epsg(4326)?;
+        projected.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        wgs84.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        let (mut xs, mut ys) = ([centre.x], [centre.y]);
+        let round_trip = CoordTransform::new(&projected, &wgs84)?
+            .transform_coords(&mut xs, &mut ys, &mut [0.])
+            .and_then(|()| {
+                CoordTransform::new(&wgs84, &projected)?
+                    .transform_coords(&mut xs, &mut ys, &mut [0.])
+            });
+        let drift = (xs[0] - centre.x).hypot(ys[0] - centre.y);
+        match round_trip {
+            Ok(()) if drift <= GEOREF_ROUND_TRIP_TOLERANCE => Ok(()),
+            Ok(()) => Err(Sentinel2ArrayError::GeoReferenceInconsistent(format!(
+                "({}, {}) drifts by {drift} over a round trip through WGS84",
+                centre.x, centre.y
+            ))),
+            Err(error) => Err(Sentinel2ArrayError::GeoReferenceInconsistent(format!(
+                "({}, {}) doesn't transform to WGS84 and back: {error}",
+                centre.x, centre.y
+            ))),
+        }
+    }
+
+    /// Check that the bands of each resolution share one pixel grid, which
+    /// reads take for granted. Fails with `MisalignedBands` for the first band,
+    /// coarsest resolution first, whose grid is scaled, rotated or offset by a
+    /// fraction of a pixel from the first of its resolution, see
+    /// [`BandInfo::grid_order`], past `BAND_ALIGNMENT_TOLERANCE` pixels. Bands
+    /// may start whole pixels apart, and bands of different resolutions
+    /// aren't compared.
+    pub fn validate_alignment(&self) -> Result<()> {
+        let by_resolution = self
+            .bands
+            .iter_sorted()
+            .into_group_map_by(|(_, band_info)| band_info.resolution());
+        for resolution in by_resolution.keys().sorted().rev() {
+            let group = &by_resolution[resolution];
+            let (reference_name, reference) = group
+                .iter()
+                .min_by(|(_, a), (_, b)| a.grid_order(b))
+                .expect("bands of the resolution");
+            let inverse_reference = reference.geo_transform().try_inverse().ok_or_else(|| {
+                Sentinel2ArrayError::BandTransformNotInvertible(reference_name.to_string())
+            })?;
+            for (band_name, band_info) in group {
+                // Grid of the band in pixels of the reference's.
+                let relative = inverse_reference * band_info.geo_transform();
+                if (relative.fixed_view::<2, 2>(0, 0) - Matrix2::identity()).amax()
+                    > BAND_ALIGNMENT_TOLERANCE
+                    || (relative.m13 - relative.m13.round()).abs() > BAND_ALIGNMENT_TOLERANCE
+                    || (relative.m23 - relative.m23.round()).abs() > BAND_ALIGNMENT_TOLERANCE
+                {
+                    return Err(Sentinel2ArrayError::MisalignedBands {
+                        band: band_name.to_string(),
+                        reference: reference_name.to_string(),
+                        offset: (relative.m13, relative.m23),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `(min_lon, min_lat, max_lon, max_lat)` WGS84 bounding box of the
+    /// corners of the [`Raster::valid_extent`] of `band_name`.
+    pub fn valid_extent_lonlat(&self, band_name: &str) -> Result<(f64, f64, f64, f64)> {
+        let (offset, (width, height)) = self.valid_extent(band_name)?;
+        let band_info = self.get_band_info(band_name)?;
+        let transform = window_transform(band_info.geo_transform(), offset);
+        lonlat_bounds(transform, (width, height), band_info.crs())
+    }
+
+    /// `(min_x, min_y, max_x, max_y)` of the highest resolution grid, in the
+    /// raster's projection.
+    pub fn bounds(&self) -> Result<(f64, f64, f64, f64)> {
+        let grid = self.grid()?;
+        let (xs, ys) = grid_corners(grid.transform, grid.size);
+        let (min_x, max_x) = xs.into_iter().minmax().into_option().unwrap();
+        let (min_y, max_y) = ys.into_iter().minmax().into_option().unwrap();
+        Ok((min_x, min_y, max_x, max_y))
+    }
+
+    /// `(min_lon, min_lat, max_lon, max_lat)` bounding the corners of the
+    /// highest resolution grid in WGS 84 (`EPSG:4326`).
+    pub fn bounds_lonlat(&self) -> Result<(f64, f64, f64, f64)> {
+        let grid = self.grid()?;
+        lonlat_bounds(grid.transform, grid.size, &grid.crs)
+    }
+
+    /// Read many windows of `band_name` through a single open dataset, which
+    /// saves reopening it for every window as `read_bands` does.
+    ///
+    /// Windows are on the band's own grid, and the arrays are returned in
+    /// their order.
+    pub fn read_band_windows(
+        &self,
+        band_name: &str,
+        windows: &[RasterWindow],
+    ) -> Result<Vec<Array2<u16>>> {
+        let reader = self.get_band_info(band_name)?.open_reader()?;
+        windows
+            .iter()
+            .map(|&(offset, window)| {
+                reader
+                    .read_as_array(offset, window)
+                    .map_err(Sentinel2ArrayError::RastersError)
+            })
+            .collect()
+    }
+
+    /// Like `read_bands`, but in the `(band, row, col)`, i.e. `(band, y, x)`,
+    /// order of numpy and rasterio instead of `(band, x, y)`.
+    pub fn read_bands_rowcol<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.read_bands(band_names, offset, window)
+            .map(|array| array.permuted_axes([0, 2, 1]).as_standard_layout().into_owned())
+    }
+
+    /// Like `read_bands`, but as a table with a row per pixel, in row major
+    /// order, and `x`, `y` columns of pixel coordinates within the window
+    /// followed by a column per band.
+    #[cfg(feature = "arrow")]
+    pub fn read_bands_arrow<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<RecordBatch> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let array = self.read_bands(band_names.clone(), offset, window)?;
+        let pixels = || (0..window.1).flat_map(|y| (0..window.0).map(move |x| (x, y)));
+        let mut fields = vec![
+            Field::new("x", DataType::UInt32, false),
+            Field::new("y", DataType::UInt32, false),
+        ];
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt32Array::from_iter_values(pixels().map(|(x, _)| x as u32))),
+            Arc::new(UInt32Array::from_iter_values(pixels().map(|(_, y)| y as u32))),
+        ];
+        for (band_name, band) in band_names.into_iter().zip(array.outer_iter()) {
+            fields.push(Field::new(band_name, DataType::UInt16, false));
+            columns.push(Arc::new(UInt16Array::from_iter_values(
+                pixels().map(|(x, y)| band[[x, y]]),
+            )));
+        }
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+    }
+
+    /// Like `read_bands_arrow`, but as a polars data frame with `lon` and
+    /// `lat` columns of the WGS84 coordinates of the pixel centres between the
+    /// pixel coordinates and the bands.
+    #[cfg(feature = "polars")]
+    pub fn read_bands_dataframe<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<DataFrame> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let (array, xs, ys) = self.read_bands_coords(&band_names, offset, window)?;
+        let pixels = || (0..window.1).flat_map(|y| (0..window.0).map(move |x| (x, y)));
+        let mut lons = pixels().map(|(x, _)| xs[x]).collect::<Vec<_>>();
+        let mut lats = pixels().map(|(_, y)| ys[y]).collect::<Vec<_>>();
+        let mut from = SpatialRef::from_wkt(self.get_band_info(band_names[0])?.crs())?;
+        let mut to = SpatialRef::from_epsg(4326)?;
+        from.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        to.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        let mut zs = vec![0.; lons.len()];
+        CoordTransform::new(&from, &to)?.transform_coords(&mut lons, &mut lats, &mut zs)?;
+
+        let mut columns = vec![
+            Column::new("x".into(), pixels().map(|(x, _)| x as u32).collect::<Vec<_>>()),
+            Column::new("y".into(), pixels().map(|(_, y)| y as u32).collect::<Vec<_>>()),
+            Column::new("lon".into(), lons),
+            Column::new("lat".into(), lats),
+        ];
+        for (band_name, band) in band_names.into_iter().zip(array.outer_iter()) {
+            let values = pixels().map(|(x, y)| band[[x, y]]).collect::<Vec<_>>();
+            columns.push(Column::new(band_name.into(), values));
+        }
+        Ok(DataFrame::new(columns)?)
+    }
+
+    /// Read `band_names` over `window` once and split it into `patch` sized
+    /// `(band, x, y)` stacks every `stride` pixels, patch rows top to bottom
+    /// and patches left to right in a row. Panics if `stride` has a zero.
+    pub fn read_patches<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        patch: (usize, usize),
+        stride: (usize, usize),
+        edges: EdgePatches,
+    ) -> Result<Vec<Array3<u16>>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.read_bands(band_names, offset, window)
+            .map(|array| split_patches(&array, patch, stride, edges))
+    }
+
+    /// Like `read_bands`, but serialized as little endian `u16`s whatever the
+    /// host's endianness. Bands follow each other in requested order, each
+    /// laid out row-major, i.e. pixel `(x, y)` of band `c` starts at byte
+    /// `2 * ((c * height + y) * width + x)`.
+    pub fn read_bands_le_bytes<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Vec<u8>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.read_bands(band_names, offset, window)
+            .map(|array| to_le_bytes(&array))
+    }
+
+    /// Like `read_bands`, but with every value of a band converted by `converter`.
+    pub fn read_bands_convert<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        converter: &impl PixelConverter,
+    ) -> Result<Array3<f32>> {
+        self.read_bands_as(band_names, offset, window, |band_name, value| {
+            converter.convert(band_name, value)
+        })
+    }
+
+    /// Like `read_bands`, but with every value of a band mapped by `convert`,
+    /// which is given the band's name along with the value.
+    pub fn read_bands_as<T, N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        convert: impl Fn(&str, u16) -> T,
+    ) -> Result<Array3<T>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let array = self.read_bands(&band_names, offset, window)?;
+        Ok(Array3::from_shape_fn(array.dim(), |(c, x, y)| {
+            convert(band_names[c], array[[c, x, y]])
+        }))
+    }
+
+    /// Like `read_bands`, but onto `window` of the grid with geo transform
+    /// `grid` instead of the highest resolution one of `band_names`.
+    pub(crate) fn read_bands_on_grid(
+        &self,
+        band_names: &[&str],
+        grid: PixelTransform,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<u16>> {
+        let bands_info = self.get_bands_info(band_names)?;
+        read_bands_info_on_grid(
+            &bands_info,
+            grid,
+            offset,
+            window,
+            Resampling::Nearest,
+            PixelAnchor::default(),
+        )
+    }
+
+    /// `band_name` over the part of its own grid under `window` of `grid`,
+    /// together with the transform from pixels of the window to pixels of the
+    /// returned array. Fails with `WindowOutOfBounds` unless the window lies
+    /// within `grid`.
+    pub(crate) fn read_band_under_grid(
+        &self,
+        band_name: &str,
+        grid: &Grid,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array2<u16>, PixelTransform)> {
+        let band_info = self.get_band_info(band_name)?;
+        check_within(offset, window, grid.size)?;
+        let transform = band_info
+            .geo_transform()
+            .try_inverse()
+            .ok_or_else(|| Sentinel2ArrayError::BandTransformNotInvertible(band_name.into()))?
+            * grid.transform;
+        let (cor_off, cor_size) =
+            transform_window((offset, window), transform, band_info.raster_size());
+        read_band(band_info, cor_off, cor_size).map(|array| (array, transform))
+    }
+
+    /// Read `band_names` over the whole extent, see [`Raster::size`].
+    pub fn read_full<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+    ) -> Result<Array3<u16>> {
+        self.read().bands(band_names).full_extent().execute()
+    }
+
+    /// Read `band_names` onto the highest resolution grid of the raster over
+    /// its whole extent, in strips of `FULL_READ_CHUNK_ROWS` rows so that only
+    /// one strip of every band is buffered besides the output.
+    pub(crate) fn read_full_chunked(
+        &self,
+        band_names: &[&str],
+        resampling: Resampling,
+        anchor: PixelAnchor,
+    ) -> Result<Array3<u16>> {
+        let bands_info = self.get_bands_info(band_names)?;
+        let highest_resolution_band = self.highest_resolution_band()?;
+        let grid = highest_resolution_band.geo_transform();
+        let (width, height) = highest_resolution_band.raster_size();
+        let mut array = Array3::zeros((bands_info.len(), width, height));
+        for row in (0..height).step_by(FULL_READ_CHUNK_ROWS) {
+            let rows = FULL_READ_CHUNK_ROWS.min(height - row);
+            let chunk = read_bands_info_on_grid(
+                &bands_info,
+                grid,
+                (0, row as isize),
+                (width, rows),
+                resampling,
+                anchor,
+            )?;
+            array.slice_mut(s![.., .., row..row + rows]).assign(&chunk);
+        }
+        Ok(array)
+    }
+
+    /// Read `band_name` over `window` with every value `v` recoded to
+    /// `lut[v]`, e.g. to reclassify classes or apply a gamma ramp.
+    pub fn read_band_lut(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+        lut: &[u16; 65536],
+    ) -> Result<Array2<u16>> {
+        self.read_band(band_name, offset, window)
+            .map(|band| band.mapv(|value| lut[value as usize]))
+    }
+
+    /// `(offset, window)`s of `tile` sized tiles covering the highest
+    /// resolution grid, rows top to bottom and tiles left to right in a row,
+    /// to feed `read_bands` with. Tiles of the last row and column are shrunk
+    /// to the raster's extent. Panics if `tile` has a zero.
+    pub fn tiles(&self, tile: (usize, usize)) -> Result<impl Iterator<Item = RasterWindow>> {
+        self.size().map(|size| tile_windows(size, tile))
+    }
+
+    /// `(offset, window)`s of the GDAL blocks of `band_name`, see
+    /// [`BandInfo::block_size`], on the band's own grid and in the order of
+    /// `tiles`, so that reading them all reads every block exactly once. The
+    /// last row and column are shrunk to the band's extent.
+    pub fn blocks(&self, band_name: &str) -> Result<impl Iterator<Item = RasterWindow>> {
+        let band_info = self.get_band_info(band_name)?;
+        Ok(tile_windows(band_info.raster_size(), band_info.block_size()?))
+    }
+
+    /// Rows of `read_bands` over `window` from top to bottom, as `(band, x)`
+    /// arrays. Rows get read in blocks of `SCANLINE_BLOCK_ROWS`, so only one
+    /// block is in memory whatever the window's height.
+    pub fn scanlines<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> impl Iterator<Item = Result<Array2<u16>>> + '_ {
+        let band_names = band_names
+            .into_iter()
+            .map(|band_name| band_name.as_ref().to_string())
+            .collect::<Vec<_>>();
+        (0..window.1)
+            .step_by(SCANLINE_BLOCK_ROWS)
+            .flat_map(move |row| {
+                let rows = SCANLINE_BLOCK_ROWS.min(window.1 - row);
+                let block_offset = (offset.0, offset.1 + row as isize);
+                match self.read_bands(&band_names, block_offset, (window.0, rows)) {
+                    Ok(block) => Either::Left(
+                        (0..rows).map(move |y| Ok(block.index_axis(Axis(2), y).to_owned())),
+                    ),
+                    Err(error) => Either::Right(iter::once(Err(error))),
+                }
+            })
+    }
+
+    /// Like `read_bands`, but with `policy` deciding what happens when some of
+    /// the bands fail to read. Under `PartialPolicy::BestEffort`, the bands
+    /// that failed are left at 0 in the stack and reported.
+    pub fn read_bands_partial<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        policy: PartialPolicy,
+    ) -> Result<PartialRead> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        if policy == PartialPolicy::FailFast {
+            return self.read_bands(&band_names, offset, window).map(|array| PartialRead {
+                array,
+                failed: Vec::new(),
+            });
+        }
+        let bands_info = self.get_bands_info(&band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        self.check_band_mismatch(&band_names, &bands_info)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let grid_crs = grid_crs(&bands_info, grid);
+        let mut failed = Vec::new();
+        let band_rasters = bands_info
+            .par_iter()
+            .map(|band_info| {
+                let no_data_value = band_info.no_data_value().map(|value| value as u16);
+                read_band_raster(band_info, grid, grid_crs, offset, window)
+                    .map(|(array, transform)| (array, transform, no_data_value))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .zip(band_names)
+            .map(|(band_raster, band_name)| {
+                band_raster
+                    .map_err(|error| failed.push((band_name.to_string(), error)))
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        let array = resample_band_rasters(
+            &band_rasters,
+            window,
+            Resampling::Nearest,
+            PixelAnchor::default(),
+        );
+        Ok(PartialRead { array, failed })
+    }
+
+    /// Like `read_bands`, along with a mask of the valid pixels. Pixels are
+    /// invalid where they hold their band's GDAL no data value, or where they
+    /// got filled in from the nearest edge pixel because the window reaches
+    /// past the band.
+    pub fn read_bands_masked<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array3<u16>, Array3<bool>)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let array = self.read_bands_unchecked::<u16>(&band_names, &bands_info, offset, window)?;
+        let inside = band_coverage(&bands_info, offset, array.dim())?;
+        let no_data_values = bands_info
+            .iter()
+            .map(|band_info| band_info.no_data_value().map(|value| value as u16))
+            .collect::<Vec<_>>();
+        let valid = Array3::from_shape_fn(array.dim(), |(c, x, y)| {
+            inside[[c, x, y]] && Some(array[[c, x, y]]) != no_data_values[c]
+        });
+        Ok((array, valid))
+    }
+
+    /// Like `read_bands`, but letting the window reach past the bands, with
+    /// the pixels there set to `fill`.
+    pub fn read_bands_filled<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        fill: u16,
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let mut array =
+            self.read_bands_unchecked::<u16>(&band_names, &bands_info, offset, window)?;
+        self.fill_outside(&band_names, offset, &mut array, fill)?;
+        Ok(array)
+    }
+
+    /// Set the pixels of `array`, a stack of `band_names` read at `offset`,
+    /// that lie past their band to `fill`.
+    pub(crate) fn fill_outside(
+        &self,
+        band_names: &[&str],
+        offset: (isize, isize),
+        array: &mut Array3<u16>,
+        fill: u16,
+    ) -> Result<()> {
+        let bands_info = self.get_bands_info(band_names)?;
+        let inside = band_coverage(&bands_info, offset, array.dim())?;
+        array.zip_mut_with(&inside, |value, &inside| {
+            if !inside {
+                *value = fill;
+            }
+        });
+        Ok(())
+    }
+
+    /// Like `read_bands`, but skips the requested bands the raster lacks.
+    ///
+    /// Returns the stack of the present bands, in requested order, together
+    /// with the names of the absent ones.
+    pub fn read_bands_present<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array3<u16>, Vec<String>)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let mut present_band_names = Vec::with_capacity(band_names.len());
+        let mut bands_info = Vec::with_capacity(band_names.len());
+        let mut absent_band_names = Vec::new();
+        for band_name in band_names {
+            match self.get_band_info(band_name) {
+                Ok(band_info) => {
+                    present_band_names.push(band_name);
+                    bands_info.push(band_info);
+                }
+                Err(_) => absent_band_names.push(band_name.to_string()),
+            }
+        }
+        if bands_info.is_empty() {
+            return Err(Sentinel2ArrayError::NoBandsFound);
+        }
+        check_within(offset, window, grid_size(&bands_info))?;
+        self.read_bands_unchecked(&present_band_names, &bands_info, offset, window)
+            .map(|array| (array, absent_band_names))
+    }
+
+    /// Like `read_bands`, but gives up with `ReadTimeout` after `timeout`.
+    ///
+    /// The read runs on a worker thread of its own. Past the deadline that
+    /// thread is abandoned rather than cancelled, so the pending GDAL read may
+    /// still hold on to its connection and memory until it returns.
+    pub fn read_bands_timeout<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        timeout: Duration,
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        let bands_info = bands_info
+            .into_iter()
+            .map(BandInfo::detached)
+            .collect::<Vec<BandInfo<()>>>();
+        run_with_timeout(timeout, move || {
+            read_bands_info(&bands_info.iter().collect::<Vec<_>>(), offset, window)
+        })
+    }
+
+    /// Like `read_bands`, but as a future running the read on tokio's blocking
+    /// thread pool, so that it doesn't hold up the async runtime's workers. It
+    /// must be awaited within a tokio runtime.
+    ///
+    /// The read goes through a copy of the raster holding only `band_names`,
+    /// which opens (or reuses) its own GDAL datasets on the blocking thread,
+    /// see `BandGroup::datasets`. Concurrent reads thus never share a dataset.
+    /// Dropping the future doesn't cancel a read that started, which runs to
+    /// completion before its thread is free again.
+    #[cfg(feature = "async")]
+    pub fn read_bands_async<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> impl std::future::Future<Output = Result<Array3<u16>>> + Send + 'static {
+        let band_names = band_names
+            .into_iter()
+            .map(|band_name| band_name.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let detached = self.detached(&band_names.iter().map(String::as_str).collect::<Vec<_>>());
+        async move {
+            let raster = detached?;
+            let read = move || raster.read_bands(band_names, offset, window);
+            match tokio::task::spawn_blocking(read).await {
+                Ok(result) => result,
+                Err(error) if error.is_panic() => panic::resume_unwind(error.into_panic()),
+                Err(error) => Err(std::io::Error::from(error).into()),
+            }
+        }
+    }
+
+    /// Copy of the raster with only `band_names`, without metadata, to move
+    /// onto other threads. It shares the band groups, and reads the same.
+    pub(crate) fn detached(&self, band_names: &[&str]) -> Result<Raster<Detached>> {
+        let bands = band_names
+            .iter()
+            .map(|&band_name| {
+                self.get_band_info(band_name)
+                    .map(|band_info| (band_name.to_string(), band_info.detached()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Raster::new(bands, ())
+            .with_band_mismatch(self.band_mismatch)
+            .with_read_tile_pixels(self.read_tile_pixels))
+    }
+
+    /// Read `band_names` into an in-memory (`MEM` driver) GDAL dataset
+    /// georeferenced to the read window, with bands named in their
+    /// `BANDNAME` metadata, ready for other GDAL operations.
+    pub fn read_to_mem_dataset<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Dataset> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        check_within(offset, window, grid_size(&bands_info))?;
+        let transform = window_transform(highest_resolution_transform(&bands_info), offset);
+        let array = read_bands_info::<u16, _>(&bands_info, offset, window)?;
+
+        let driver = DriverManager::get_driver_by_name("MEM")?;
+        let mut dataset =
+            driver.create_with_band_type::<u16, _>("", window.0, window.1, bands_info.len())?;
+        dataset.set_geo_transform(&transform_to_gdal(&transform))?;
+        dataset.set_projection(bands_info[0].crs())?;
+        for (index, (band_name, band)) in band_names.iter().zip(array.outer_iter()).enumerate() {
+            // Arrays are indexed `[[x, y]]`, GDAL buffers are row-major.
+            let mut buffer = Buffer::new(window, band.t().iter().copied().collect());
+            let mut raster_band = dataset.rasterband(index + 1)?;
+            raster_band.write((0, 0), window, &mut buffer)?;
+            raster_band.set_metadata_item("BANDNAME", band_name, "")?;
+        }
+        Ok(dataset)
+    }
+
+    /// Read `band_names` over the window and bilinearly warp them into
+    /// projection `target_crs` (any definition GDAL takes, e.g. `EPSG:4326`),
+    /// onto a north-up grid bounding the window's footprint at about the same
+    /// pixel count along its diagonal. Pixels outside of the footprint are the
+    /// bands' no data value, 0 if they have none.
+    pub fn read_bands_reproject<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        target_crs: &str,
+    ) -> Result<(Array3<u16>, PixelTransform)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let source_transform = window_transform(highest_resolution_transform(&bands_info), offset);
+        let no_data_value = bands_info[0].no_data_value().unwrap_or_default();
+
+        // The footprint's edges, sampled densely enough to follow their curvature.
+        const EDGE_SAMPLES: usize = 16;
+        let (width, height) = (window.0 as f64, window.1 as f64);
+        let edge_points = (0..=EDGE_SAMPLES)
+            .flat_map(|step| {
+                let fraction = step as f64 / EDGE_SAMPLES as f64;
+                [
+                    (fraction * width, 0.),
+                    (fraction * width, height),
+                    (0., fraction * height),
+                    (width, fraction * height),
+                ]
+            })
+            .map(|(x, y)| source_transform.transform_point(&Point2::new(x, y)))
+            .collect::<Vec<_>>();
+        let mut xs = edge_points.iter().map(|point| point.x).collect::<Vec<_>>();
+        let mut ys = edge_points.iter().map(|point| point.y).collect::<Vec<_>>();
+        let target_wkt = SpatialRef::from_definition(target_crs)?.to_wkt()?;
+        let mut from = SpatialRef::from_wkt(bands_info[0].crs())?;
+        let mut to = SpatialRef::from_wkt(&target_wkt)?;
+        from.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        to.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        CoordTransform::new(&from, &to)?.transform_coords(
+            &mut xs,
+            &mut ys,
+            &mut vec![0.; edge_points.len()],
+        )?;
+
+        let (min_x, max_x) = xs.iter().copied().minmax().into_option().unwrap();
+        let (min_y, max_y) = ys.iter().copied().minmax().into_option().unwrap();
+        // The first sample of the first step and the second one of the last
+        // step are the upper left and lower right corners.
+        let (upper_left, lower_right) = (0, 4 * EDGE_SAMPLES + 1);
+        let diagonal = (xs[lower_right] - xs[upper_left]).hypot(ys[lower_right] - ys[upper_left])
+            / width.hypot(height);
+        let target_size = (
+            (((max_x - min_x) / diagonal).ceil() as usize).max(1),
+            (((max_y - min_y) / diagonal).ceil() as usize).max(1),
+        );
+        let target_transform = transform_from_gdal(&[min_x, diagonal, 0., max_y, 0., -diagonal]);
+
+        let source = self.read_to_mem_dataset(band_names.clone(), offset, window)?;
+        let driver = DriverManager::get_driver_by_name("MEM")?;
+        let mut target = driver.create_with_band_type::<u16, _>(
+            "",
+            target_size.0,
+            target_size.1,
+            band_names.len(),
+        )?;
+        target.set_geo_transform(&transform_to_gdal(&target_transform))?;
+        target.set_projection(&target_wkt)?;
+        for index in 1..=band_names.len() {
+            // The warper leaves the pixels it has no source for untouched.
+            let mut band = target.rasterband(index)?;
+            band.fill(no_data_value, None)?;
+            band.set_no_data_value(Some(no_data_value))?;
+        }
+        reproject(&source, &target, DEFAULT_WARP_MEM_LIMIT_MB)?;
+
+        let mut array = Array3::zeros((band_names.len(), target_size.0, target_size.1));
+        for (index, mut band) in array.outer_iter_mut().enumerate() {
+            let buffer = target
+                .rasterband(index + 1)?
+                .read_as::<u16>((0, 0), target_size, target_size, None)?;
+            // GDAL buffers are row-major, arrays are indexed `[[x, y]]`.
+            band.view_mut()
+                .reversed_axes()
+                .iter_mut()
+                .zip(buffer.data())
+                .for_each(|(value, &read)| *value = read);
+        }
+        Ok((array, target_transform))
+    }
+
+    /// Read `band_names` onto the grid of `reference` at its highest resolution,
+    /// bilinearly warping them into its CRS if it differs. Reference pixels
+    /// this raster doesn't cover are `NaN`.
+    pub fn read_bands_like<R: Sensor, N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        reference: &Raster<R>,
+    ) -> Result<Array3<f32>> {
+        self.read_bands_like_with_mem_limit(band_names, reference, DEFAULT_WARP_MEM_LIMIT_MB)
+    }
+
+    /// Like `read_bands_like`, but capping the memory GDAL's warper works
+    /// with to `warp_mem_limit_mb` megabytes, at the cost of more chunks.
+    pub fn read_bands_like_with_mem_limit<R: Sensor, N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        reference: &Raster<R>,
+        warp_mem_limit_mb: usize,
+    ) -> Result<Array3<f32>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.read_bands_to_grid_with_mem_limit(&band_names, &reference.grid()?, warp_mem_limit_mb)
+    }
+
+    /// Like `read_bands_like`, but onto `grid` rather than the grid of another
+    /// raster, together with the chain of transforms the read went through.
+    pub fn read_bands_to_grid_traced<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        grid: &Grid,
+    ) -> Result<(Array3<f32>, TransformChain)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let source_transform = highest_resolution_transform(&bands_info);
+        let source_crs = bands_info[0].crs().to_string();
+        let composed = if SpatialRef::from_wkt(&source_crs)? == SpatialRef::from_wkt(&grid.crs)? {
+            source_transform
+                .try_inverse()
+                .map(|inverse_source| inverse_source * grid.transform)
+        } else {
+            None
+        };
+        let chain = TransformChain {
+            source_crs,
+            target_crs: grid.crs.clone(),
+            resampling: Resampling::Bilinear,
+            source_transform,
+            target_transform: grid.transform,
+            composed,
+        };
+        self.read_bands_to_grid(&band_names, grid).map(|array| (array, chain))
+    }
+
+    /// Warp `band_names` bilinearly onto `grid`, with the default memory
+    /// limit of the warper.
+    pub(crate) fn read_bands_to_grid(
+        &self,
+        band_names: &[&str],
+        grid: &Grid,
+    ) -> Result<Array3<f32>> {
+        self.read_bands_to_grid_with_mem_limit(band_names, grid, DEFAULT_WARP_MEM_LIMIT_MB)
+    }
+
+    /// Warp `band_names` bilinearly onto `grid`, see `read_bands_like_with_mem_limit`.
+    fn read_bands_to_grid_with_mem_limit(
+        &self,
+        band_names: &[&str],
+        grid: &Grid,
+        warp_mem_limit_mb: usize,
+    ) -> Result<Array3<f32>> {
+        let (width, height) = grid.size;
+        let reference_transform = grid.transform;
+
+        let driver = DriverManager::get_driver_by_name("MEM")?;
+        let mut like =
+            driver.create_with_band_type::<f32, _>("", width, height, band_names.len())?;
+        like.set_geo_transform(&transform_to_gdal(&reference_transform))?;
+        like.set_projection(&grid.crs)?;
+        for index in 1..=band_names.len() {
+            let mut band = like.rasterband(index)?;
+            band.fill(f64::NAN, None)?;
+            band.set_no_data_value(Some(f64::NAN))?;
+        }
+        if let Some((offset, window)) = self.window_covering(
+            band_names,
+            reference_transform,
+            (width, height),
+            &grid.crs,
+        )? {
+            let source = self.read_to_mem_dataset(band_names, offset, window)?;
+            reproject(&source, &like, warp_mem_limit_mb)?;
+        }
+
+        let mut array = Array3::zeros((band_names.len(), width, height));
+        for (index, mut band) in array.outer_iter_mut().enumerate() {
+            let buffer = like
+                .rasterband(index + 1)?
+                .read_as::<f32>((0, 0), (width, height), (width, height), None)?;
+            // GDAL buffers are row-major, arrays are indexed `[[x, y]]`.
+            band.view_mut()
+                .reversed_axes()
+                .iter_mut()
+                .zip(buffer.data())
+                .for_each(|(value, &read)| *value = read);
+        }
+        Ok(array)
+    }
+
+    /// Window of the read grid of `band_names` covering a `size` pixels grid
+    /// with geo transform `transform` in projection `crs`, padded by a pixel
+    /// for interpolation. `None` if they don't overlap.
+    fn window_covering(
+        &self,
+        band_names: &[&str],
+        transform: PixelTransform,
+        size: (usize, usize),
+        crs: &str,
+    ) -> Result<Option<RasterWindow>> {
+        let bands_info = self.get_bands_info(band_names)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let grid_band = bands_info
+            .iter()
+            .find(|band_info| band_info.geo_transform() == grid)
+            .ok_or(Sentinel2ArrayError::NoBandsFound)?;
+        let inverse_grid = grid
+            .try_inverse()
+            .ok_or_else(|| Sentinel2ArrayError::BandTransformNotInvertible(band_names.join(", ")))?;
+        covering_window(grid_band, inverse_grid, transform, size, crs)
+    }
+
+    /// Read `band_names` over the bounding box from `min` to `max`, in
+    /// projection `crs` (any definition GDAL takes, e.g. `EPSG:4326` with
+    /// longitudes first) or in the raster's one if `None`. The box is clipped
+    /// to the raster's extent, and fails with `BboxOutsideRaster` if nothing
+    /// of it is left.
+    pub fn read_bands_bbox<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        min: (f64, f64),
+        max: (f64, f64),
+        crs: Option<&str>,
+    ) -> Result<Array3<u16>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let (offset, window) = self.bbox_window(&band_names, min, max, crs)?;
+        self.read_bands(band_names, offset, window)
+    }
+
+    /// Window of the highest resolution grid of `band_names` covering the
+    /// bounding box of `read_bands_bbox`, clipped to the grid.
+    pub(crate) fn bbox_window(
+        &self,
+        band_names: &[&str],
+        min: (f64, f64),
+        max: (f64, f64),
+        crs: Option<&str>,
+    ) -> Result<RasterWindow> {
+        let bands_info = self.get_bands_info(band_names)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let grid_band = bands_info
+            .iter()
+            .find(|band_info| band_info.geo_transform() == grid)
+            .ok_or(Sentinel2ArrayError::NoBandsFound)?;
+        let inverse_grid = grid
+            .try_inverse()
+            .ok_or_else(|| Sentinel2ArrayError::BandTransformNotInvertible(band_names.join(", ")))?;
+
+        let mut xs = [min.0, max.0, min.0, max.0];
+        let mut ys = [min.1, min.1, max.1, max.1];
+        if let Some(crs) = crs {
+            transform_coords_into(crs, grid_band.crs(), &mut xs, &mut ys)?;
+        }
+        let corners = xs
+            .into_iter()
+            .zip(ys)
+            .map(|(x, y)| inverse_grid.transform_point(&Point2::new(x, y)))
+            .collect::<Vec<_>>();
+        let (width, height) = grid_band.raster_size();
+        let bounds = |coordinates: Vec<f64>, extent: usize| {
+            let (min, max) = coordinates.into_iter().minmax().into_option().unwrap();
+            let min = min.floor().clamp(0., extent as f64) as usize;
+            let max = max.ceil().clamp(0., extent as f64) as usize;
+            (min, max.saturating_sub(min))
+        };
+        let (x, width) = bounds(corners.iter().map(|corner| corner.x).collect(), width);
+        let (y, height) = bounds(corners.iter().map(|corner| corner.y).collect(), height);
+        if width == 0 || height == 0 {
+            return Err(Sentinel2ArrayError::BboxOutsideRaster { min, max });
+        }
+        Ok(((x as isize, y as isize), (width, height)))
+    }
+
+    /// Values of `band_names` at the pixel of their highest resolution grid
+    /// holding `point`, in projection `crs` (any definition GDAL takes, e.g.
+    /// `EPSG:4326` with longitudes first) or in the raster's one if `None`.
+    /// Fails with `WindowOutOfBounds` for points outside of the grid.
+    pub fn sample(
+        &self,
+        band_names: &[&str],
+        point: (f64, f64),
+        crs: Option<&str>,
+    ) -> Result<Vec<u16>> {
+        let bands_info = self.get_bands_info(band_names)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let inverse_grid = grid
+            .try_inverse()
+            .ok_or_else(|| Sentinel2ArrayError::BandTransformNotInvertible(band_names.join(", ")))?;
+        let (mut xs, mut ys) = ([point.0], [point.1]);
+        if let Some(crs) = crs {
+            transform_coords_into(crs, grid_crs(&bands_info, grid), &mut xs, &mut ys)?;
+        }
+        let pixel = inverse_grid.transform_point(&Point2::new(xs[0], ys[0]));
+        let offset = (pixel.x.floor() as isize, pixel.y.floor() as isize);
+        let raster_size = grid_size(&bands_info);
+        if !(0..raster_size.0 as isize).contains(&offset.0)
+            || !(0..raster_size.1 as isize).contains(&offset.1)
+        {
+            return Err(Sentinel2ArrayError::WindowOutOfBounds {
+                offset,
+                window: (1, 1),
+                raster_size,
+            });
+        }
+        self.read_bands(band_names, offset, (1, 1))
+            .map(|array| array.into_iter().collect())
+    }
+
+    /// Read `band_names` over the smallest window holding the circle of
+    /// `radius_m` meters around `center_lonlat`, with the pixels whose centre
+    /// lies outside of it set to `NaN`. Parts of the circle past the edges of
+    /// the raster are left out of the window.
+    pub fn read_bands_circle<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        center_lonlat: (f64, f64),
+        radius_m: f64,
+    ) -> Result<Array3<f32>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let grid_size = bands_info
+            .iter()
+            .find(|band_info| band_info.geo_transform() == grid)
+            .map(|band_info| band_info.raster_size())
+            .ok_or(Sentinel2ArrayError::NoBandsFound)?;
+        let inverse_grid = grid
+            .try_inverse()
+            .ok_or_else(|| Sentinel2ArrayError::BandTransformNotInvertible(band_names.join(", ")))?;
+
+        let (mut xs, mut ys) = ([center_lonlat.0], [center_lonlat.1]);
+        let mut from = SpatialRef::from_epsg(4326)?;
+        let mut to = SpatialRef::from_wkt(bands_info[0].crs())?;
+        from.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        to.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        CoordTransform::new(&from, &to)?.transform_coords(&mut xs, &mut ys, &mut [0.])?;
+        let center = inverse_grid.transform_point(&Point2::new(xs[0], ys[0]));
+        let radius = radius_m / grid.m11.abs();
+
+        let bounds = |center: f64, extent: usize| {
+            let min = (center - radius).floor().clamp(0., extent as f64) as usize;
+            let max = (center + radius).ceil().clamp(0., extent as f64) as usize;
+            (min, max.saturating_sub(min))
+        };
+        let (x, width) = bounds(center.x, grid_size.0);
+        let (y, height) = bounds(center.y, grid_size.1);
+        if width == 0 || height == 0 {
+            return Ok(Array3::zeros((band_names.len(), width, height)));
+        }
+        let mut array =
+            read_bands_info::<u16, _>(&bands_info, (x as isize, y as isize), (width, height))?
+            .mapv(f32::from);
+        mask_outside_circle(
+            &mut array,
+            Point2::new(center.x - x as f64, center.y - y as f64),
+            radius,
+        );
+        Ok(array)
+    }
+
+    /// Write `band_names` over the read window to a Cloud Optimized GeoTIFF.
+    ///
+    /// Goes through GDAL's COG driver, which tiles the output and builds
+    /// internal overviews ahead of the full resolution data. Overviews are only
+    /// built while they are larger than a tile, so small windows get none.
+    pub fn write_cog<N: AsRef<str>, P: AsRef<Path>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        out_path: P,
+    ) -> Result<()> {
+        let dataset = self.read_to_mem_dataset(band_names, offset, window)?;
+        let driver = DriverManager::get_driver_by_name("COG")?;
+        let options = RasterCreationOptions::from_iter(["COMPRESS=DEFLATE", "OVERVIEWS=AUTO"]);
+        dataset.create_copy(&driver, out_path, &options)?.close()?;
+        Ok(())
+    }
+
+    /// Write `array`, e.g. a processed `read_bands` result, to a GeoTIFF at
+    /// `out_path`, one band per entry of its first axis, georeferenced as a
+    /// read at `offset` of the highest resolution grid.
+    pub fn write_geotiff<P: AsRef<Path>>(
+        &self,
+        array: &Array3<u16>,
+        offset: (isize, isize),
+        out_path: P,
+    ) -> Result<()> {
+        self.array_dataset("GTiff", out_path, array, offset)?.close()?;
+        Ok(())
+    }
+
+    /// Like `write_geotiff`, but to a Cloud Optimized GeoTIFF through GDAL's
+    /// COG driver, with internal overviews if `overviews`. Fails with
+    /// `CogNotTiled` if the written file doesn't reopen as a tiled COG.
+    pub fn write_array_cog<P: AsRef<Path>>(
+        &self,
+        array: &Array3<u16>,
+        offset: (isize, isize),
+        out_path: P,
+        overviews: bool,
+    ) -> Result<()> {
+        let dataset = self.array_dataset("MEM", "", array, offset)?;
+        let driver = DriverManager::get_driver_by_name("COG")?;
+        let options = RasterCreationOptions::from_iter([
+            "COMPRESS=DEFLATE",
+            if overviews { "OVERVIEWS=AUTO" } else { "OVERVIEWS=NONE" },
+        ]);
+        dataset.create_copy(&driver, &out_path, &options)?.close()?;
+
+        let written = Dataset::open(&out_path)?;
+        let tiled = written.metadata_item("LAYOUT", "IMAGE_STRUCTURE").as_deref() == Some("COG")
+            && written.rasterband(1)?.block_size().1 > 1;
+        if !tiled {
+            return Err(Sentinel2ArrayError::CogNotTiled(
+                out_path.as_ref().display().to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Write `band_names` over the read window to an uncompressed Zarr v2
+    /// store at `out_path`, e.g. for `xarray.open_zarr`.
+    ///
+    /// The store's group holds a single `bands` array of shape `(band, y, x)`,
+    /// in C order and in chunks of one band by up to `ZARR_CHUNK_PIXELS`
+    /// square pixels. Attributes follow xarray's (`_ARRAY_DIMENSIONS`) and the
+    /// GeoZarr (CF grid mapping) conventions: a WKT `crs_wkt`, the window's
+    /// GDAL `GeoTransform` and the `band_names`, on both the group and array.
+    /// Fails with `EmptyWindow` for windows without pixels.
+    #[cfg(feature = "zarr")]
+    pub fn write_zarr<N: AsRef<str>, P: AsRef<Path>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        out_path: P,
+    ) -> Result<()> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        if window.0 == 0 || window.1 == 0 {
+            return Err(Sentinel2ArrayError::EmptyWindow(window));
+        }
+        let bands_info = self.get_bands_info(&band_names)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let crs = grid_crs(&bands_info, grid);
+        let geo_transform = transform_to_gdal(&window_transform(grid, offset));
+        let array = self.read_bands(band_names.iter(), offset, window)?;
+
+        let (width, height) = window;
+        let chunk = (ZARR_CHUNK_PIXELS.min(width), ZARR_CHUNK_PIXELS.min(height));
+        let mut attributes = serde_json::json!({
+            "crs_wkt": crs,
+            "GeoTransform": geo_transform.iter().join(" "),
+            "band_names": band_names,
+        });
+        let group_path = out_path.as_ref();
+        let array_path = group_path.join("bands");
+        std::fs::create_dir_all(&array_path)?;
+        std::fs::write(group_path.join(".zgroup"), r#"{"zarr_format": 2}"#)?;
+        std::fs::write(group_path.join(".zattrs"), attributes.to_string())?;
+        attributes["_ARRAY_DIMENSIONS"] = serde_json::json!(["band", "y", "x"]);
+        std::fs::write(array_path.join(".zattrs"), attributes.to_string())?;
+        let metadata = serde_json::json!({
+            "zarr_format": 2,
+            "shape": [band_names.len(), height, width],
+            "chunks": [1, chunk.1, chunk.0],
+            "dtype": "<u2",
+            "compressor": null,
+            "fill_value": 0,
+            "order": "C",
+            "filters": null,
+        });
+        std::fs::write(array_path.join(".zarray"), metadata.to_string())?;
+
+        // Chunks are written whole, edge ones padded with the fill value.
+        for (band_index, band) in array.outer_iter().enumerate() {
+            for ((x, y), _) in tile_windows(window, chunk) {
+                let (x, y) = (x as usize, y as usize);
+                let mut bytes = Vec::with_capacity(chunk.0 * chunk.1 * 2);
+                for row in y..y + chunk.1 {
+                    for col in x..x + chunk.0 {
+                        let value = band.get((col, row)).copied().unwrap_or_default();
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                let key = format!("{band_index}.{}.{}", y / chunk.1, x / chunk.0);
+                std::fs::write(array_path.join(key), bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `band_names` over the read window to a CF-1.7 netCDF file at
+    /// `out_path`, in the classic 64-bit offset format, e.g. for
+    /// `xarray.open_dataset`.
+    ///
+    /// Each band is a variable of its own named after it, of dimensions
+    /// `(y, x)`, with pixel centre coordinates `x` increasing and `y`
+    /// decreasing, in projection units. The bands are stored as `short` with
+    /// `_Unsigned = "true"`, which readers turn back into `u16`, and carry
+    /// their no data value, 0 if they have none, as `_FillValue`. Their
+    /// `grid_mapping`, the scalar `crs` variable, describes the projection by
+    /// its WKT, as `crs_wkt` and GDAL's `spatial_ref`, and the window's
+    /// `GeoTransform`. Coordinates take the grid to be north up, as
+    /// Sentinel-2 ones are.
+    #[cfg(feature = "netcdf")]
+    pub fn write_netcdf<N: AsRef<str>, P: AsRef<Path>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        out_path: P,
+    ) -> Result<()> {
+        use super::netcdf::{NetcdfFile, Values, Variable};
+
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let bands_info = self.get_bands_info(&band_names)?;
+        let grid = highest_resolution_transform(&bands_info);
+        let crs = grid_crs(&bands_info, grid);
+        let read_grid = window_transform(grid, offset);
+        let array = self.read_bands(band_names.iter(), offset, window)?;
+
+        let text = |text: &str| Values::Char(text.into());
+        let coordinate = |name: &str, dimension, standard_name: &str, values| Variable {
+            name: name.into(),
+            dimensions: vec![dimension],
+            attributes: vec![
+                ("standard_name".into(), text(standard_name)),
+                ("units".into(), text("m")),
+                ("axis".into(), text(&name.to_uppercase())),
+            ],
+            values: Values::Double(values),
+        };
+        let (width, height) = window;
+        let xs = (0..width).map(|x| read_grid.m13 + (x as f64 + 0.5) * read_grid.m11);
+        let ys = (0..height).map(|y| read_grid.m23 + (y as f64 + 0.5) * read_grid.m22);
+        let mut variables = vec![
+            coordinate("y", 0, "projection_y_coordinate", ys.collect()),
+            coordinate("x", 1, "projection_x_coordinate", xs.collect()),
+            Variable {
+                name: "crs".into(),
+                dimensions: Vec::new(),
+                attributes: vec![
+                    ("crs_wkt".into(), text(crs)),
+                    ("spatial_ref".into(), text(crs)),
+                    (
+                        "GeoTransform".into(),
+                        text(&transform_to_gdal(&read_grid).iter().join(" ")),
+                    ),
+                ],
+                values: Values::Int(vec![0]),
+            },
+        ];
+        for ((band_name, band_info), band) in
+            band_names.iter().zip(&bands_info).zip(array.outer_iter())
+        {
+            let fill_value = band_info.no_data_value().map_or(0, |value| value as u16);
+            // `u16` values are stored as the `i16` of the same bits.
+            variables.push(Variable {
+                name: band_name.to_string(),
+                dimensions: vec![0, 1],
+                attributes: vec![
+                    ("long_name".into(), text(band_name)),
+                    ("grid_mapping".into(), text("crs")),
+                    ("_Unsigned".into(), text("true")),
+                    ("_FillValue".into(), Values::Short(vec![fill_value as i16])),
+                ],
+                // Arrays are indexed `[[x, y]]`, netCDF variables row by row.
+                values: Values::Short(band.t().iter().map(|&value| value as i16).collect()),
+            });
+        }
+        let file = NetcdfFile {
+            dimensions: vec![("y".into(), height), ("x".into(), width)],
+            attributes: vec![("Conventions".into(), text("CF-1.7"))],
+            variables,
+        };
+        let mut writer = BufWriter::new(File::create(out_path)?);
+        file.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Dataset of driver `driver_name` at `path` holding `array`,
+    /// georeferenced as in `write_geotiff`.
+    fn array_dataset<P: AsRef<Path>>(
+        &self,
+        driver_name: &str,
+        path: P,
+        array: &Array3<u16>,
+        offset: (isize, isize),
+    ) -> Result<Dataset> {
+        let grid = self.grid()?;
+        let (bands, width, height) = array.dim();
+        let driver = DriverManager::get_driver_by_name(driver_name)?;
+        let mut dataset = driver.create_with_band_type::<u16, _>(path, width, height, bands)?;
+        dataset.set_geo_transform(&transform_to_gdal(&window_transform(grid.transform, offset)))?;
+        dataset.set_projection(&grid.crs)?;
+        for (index, band) in array.outer_iter().enumerate() {
+            // Arrays are indexed `[[x, y]]`, GDAL buffers are row-major.
+            let mut buffer = Buffer::new((width, height), band.t().iter().copied().collect());
+            dataset.rasterband(index + 1)?.write((0, 0), (width, height), &mut buffer)?;
+        }
+        Ok(dataset)
+    }
+
+    /// Stream a band at its native resolution to `out_path` and memory-map it.
+    ///
+    /// The band is read in row-blocks and written as native-endian `u16` in
+    /// row-major order, so pixel `(x, y)` sits at byte `(y * width + x) * 2`.
+    /// The file at `out_path` is created (or truncated) by this call and is
+    /// left on disk once the returned map is dropped; removing it is up to the
+    /// caller. The file must not be modified while mapped.
+    #[cfg(feature = "mmap")]
+    pub fn read_band_mmap<P: AsRef<Path>>(&self, band_name: &str, out_path: P) -> Result<Mmap> {
+        const BLOCK_ROWS: usize = 512;
+
+        let band_info = self.get_band_info(band_name)?;
+        let (width, height) = band_info.raster_size();
+        let reader = band_info.reader();
+        let mut writer = BufWriter::new(File::create(&out_path)?);
+        for row in (0..height).step_by(BLOCK_ROWS) {
+            let rows = BLOCK_ROWS.min(height - row);
+            let block: Array2<u16> = reader.read_as_array((0, row as isize), (width, rows))?;
+            // Arrays are indexed `[[x, y]]`, walk them row by row.
+            for value in block.t().iter() {
+                writer.write_all(&value.to_ne_bytes())?;
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+
+        let file = File::open(out_path)?;
+        // SAFETY: the file was just written by us and is documented as
+        // off-limits for modification while the map is alive.
+        Ok(unsafe { Mmap::map(&file)? })
+    }
+}
+
+/// Geo transform of the finest grid of `bands_info`, which mustn't be empty.
+fn highest_resolution_transform<BM>(bands_info: &[&BandInfo<BM>]) -> PixelTransform {
+    finest_band(bands_info)
+        .expect("bands to read, see `Raster::get_bands_info`")
+        .geo_transform()
+}
+
+/// Band of `bands_info` with the finest grid, see [`BandInfo::grid_order`].
+fn finest_band<'a, BM>(bands_info: &[&'a BandInfo<BM>]) -> Option<&'a BandInfo<BM>> {
+    bands_info.iter().copied().min_by(|a, b| a.grid_order(b))
+}
+
+/// Coordinates of the corners of a `size` pixels grid with geo transform
+/// `transform`, as `xs` and `ys`.
+fn grid_corners(transform: PixelTransform, size: (usize, usize)) -> ([f64; 4], [f64; 4]) {
+    let (width, height) = size;
+    let corners = [(0, 0), (width, 0), (0, height), (width, height)]
+        .map(|(x, y)| transform.transform_point(&Point2::new(x as f64, y as f64)));
+    (corners.map(|corner| corner.x), corners.map(|corner| corner.y))
+}
+
+/// `(min_lon, min_lat, max_lon, max_lat)` bounding the corners of a `size`
+/// pixels grid with geo transform `transform` in WKT projection `crs`.
+fn lonlat_bounds(
+    transform: PixelTransform,
+    size: (usize, usize),
+    crs: &str,
+) -> Result<(f64, f64, f64, f64)> {
+    let (mut lons, mut lats) = grid_corners(transform, size);
+    let mut from = SpatialRef::from_wkt(crs)?;
+    let mut to = SpatialRef::from_epsg(4326)?;
+    from.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    to.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    CoordTransform::new(&from, &to)?.transform_coords(&mut lons, &mut lats, &mut [0.; 4])?;
+    let (min_lon, max_lon) = lons.into_iter().minmax().into_option().unwrap();
+    let (min_lat, max_lat) = lats.into_iter().minmax().into_option().unwrap();
+    Ok((min_lon, min_lat, max_lon, max_lat))
+}
+
+/// Transform the coordinates `xs` and `ys` from projection `crs`, any
+/// definition GDAL takes, into the one of WKT `wkt`, longitudes first.
+fn transform_coords_into(crs: &str, wkt: &str, xs: &mut [f64], ys: &mut [f64]) -> Result<()> {
+    let mut from = SpatialRef::from_definition(crs)?;
+    let mut to = SpatialRef::from_wkt(wkt)?;
+    if from != to {
+        from.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        to.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        CoordTransform::new(&from, &to)?.transform_coords(xs, ys, &mut vec![0.; xs.len()])?;
+    }
+    Ok(())
+}
+
+/// Size of the highest resolution grid of `bands_info`.
+fn grid_size<BM>(bands_info: &[&BandInfo<BM>]) -> (usize, usize) {
+    finest_band(bands_info).map_or((0, 0), BandInfo::raster_size)
+}
+
+/// Which pixels of a `(band, x, y)` stack of `bands_info` read at `offset` of
+/// their highest resolution grid have their centre within their band.
+fn band_coverage<BM>(
+    bands_info: &[&BandInfo<BM>],
+    offset: (isize, isize),
+    dim: (usize, usize, usize),
+) -> Result<Array3<bool>> {
+    let window_grid = window_transform(highest_resolution_transform(bands_info), offset);
+    let band_transforms = bands_info
+        .iter()
+        .map(|band_info| {
+            inverse_band_transform(band_info)
+                .map(|inverse| (inverse * window_grid, band_info.raster_size()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Array3::from_shape_fn(dim, |(c, x, y)| {
+        let (transform, (width, height)) = band_transforms[c];
+        let point = transform.transform_point(&PixelAnchor::default().point(x, y));
+        (0. ..width as f64).contains(&point.x) && (0. ..height as f64).contains(&point.y)
+    }))
+}
+
+/// Fail with `WindowOutOfBounds` unless the window at `offset` of `window`
+/// pixels lies within a grid of `raster_size` pixels.
+fn check_within(
+    offset: (isize, isize),
+    window: (usize, usize),
+    raster_size: (usize, usize),
+) -> Result<()> {
+    let within = |offset: isize, window: usize, size: usize| {
+        offset >= 0 && offset as usize + window <= size
+    };
+    if !(within(offset.0, window.0, raster_size.0) && within(offset.1, window.1, raster_size.1)) {
+        return Err(Sentinel2ArrayError::WindowOutOfBounds {
+            offset,
+            window,
+            raster_size,
+        });
+    }
+    Ok(())
+}
+
+/// Part of the window at `offset` of `window` pixels within a grid of `size`
+/// pixels, `None` if nothing is.
+pub(crate) fn clip_window(
+    offset: (isize, isize),
+    window: (usize, usize),
+    size: (usize, usize),
+) -> Option<RasterWindow> {
+    let clip = |offset: isize, window: usize, size: usize| {
+        let start = offset.clamp(0, size as isize);
+        let end = (offset + window as isize).clamp(0, size as isize);
+        (start < end).then_some((start, (end - start) as usize))
+    };
+    let (x, width) = clip(offset.0, window.0, size.0)?;
+    let (y, height) = clip(offset.1, window.1, size.1)?;
+    Some(((x, y), (width, height)))
+}
+
+/// Window of the grid of `band_info`, whose geo transform inverts to
+/// `inverse_grid`, covering a `size` pixels grid with geo transform
+/// `transform` in projection `crs`, padded by a pixel for interpolation.
+/// `None` if they don't overlap.
+fn covering_window<BM>(
+    band_info: &BandInfo<BM>,
+    inverse_grid: PixelTransform,
+    transform: PixelTransform,
+    size: (usize, usize),
+    crs: &str,
+) -> Result<Option<RasterWindow>> {
+    let grid_size = band_info.raster_size();
+    let corners = [(0, 0), (size.0, 0), (0, size.1), (size.0, size.1)]
+        .map(|(x, y)| transform.transform_point(&Point2::new(x as f64, y as f64)));
+    let mut xs = corners.map(|corner| corner.x);
+    let mut ys = corners.map(|corner| corner.y);
+    let mut from = SpatialRef::from_wkt(crs)?;
+    let mut to = SpatialRef::from_wkt(band_info.crs())?;
+    from.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    to.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    CoordTransform::new(&from, &to)?.transform_coords(&mut xs, &mut ys, &mut [0.; 4])?;
+
+    let pixels = xs
+        .into_iter()
+        .zip(ys)
+        .map(|(x, y)| inverse_grid.transform_point(&Point2::new(x, y)))
+        .collect::<Vec<_>>();
+    let bounds = |coordinate: fn(&Point2<f64>) -> f64, extent: usize| {
+        let (min, max) = pixels
+            .iter()
+            .map(coordinate)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let min = (min.floor() - 1.).clamp(0., extent as f64) as usize;
+        let max = (max.ceil() + 1.).clamp(0., extent as f64) as usize;
+        (min < max).then_some((min, max - min))
+    };
+    Ok(bounds(|pixel| pixel.x, grid_size.0)
+        .zip(bounds(|pixel| pixel.y, grid_size.1))
+        .map(|((x, width), (y, height))| ((x as isize, y as isize), (width, height))))
+}
+
+/// Affine coefficients `(a, b, c, d, e, f)` of `transform`, see [`Profile::transform`].
+fn affine_coefficients(transform: &PixelTransform) -> [f64; 6] {
+    [
+        transform.m11,
+        transform.m12,
+        transform.m13,
+        transform.m21,
+        transform.m22,
+        transform.m23,
+    ]
+}
+
+/// Inverse of `transform_from_gdal`.
+pub fn transform_to_gdal(transform: &PixelTransform) -> GeoTransform {
+    [
+        transform.m13,
+        transform.m11,
+        transform.m12,
+        transform.m23,
+        transform.m21,
+        transform.m22,
+    ]
+}
+
+/// Transform of the window at `offset` on the grid of `transform`.
+pub(crate) fn window_transform(
+    transform: PixelTransform,
+    offset: (isize, isize),
+) -> PixelTransform {
+    transform * PixelTransform::new_translation(&Vector2::new(offset.0 as f64, offset.1 as f64))
+}
+
+/// Inverse of the geo transform of `band_info`, failing with
+/// `BandTransformNotInvertible` naming the band by its index and dataset.
+fn inverse_band_transform<BM>(band_info: &BandInfo<BM>) -> Result<PixelTransform> {
+    band_info.geo_transform().try_inverse().ok_or_else(|| {
+        Sentinel2ArrayError::BandTransformNotInvertible(format!(
+            "{} of {}",
+            band_info.index(),
+            band_info.gdal_dataset_path()
+        ))
+    })
+}
+
+fn read_band<T: GdalType + Copy, BM>(
+    band_info: &BandInfo<BM>,
+    off: (isize, isize),
+    size: (usize, usize),
+) -> Result<Array2<T>> {
+    band_info.read_cached(off, size)
+}
+
+/// Read `bands_info` over `window` of the highest resolution grid among them,
+/// resampling the coarser bands onto it.
+fn read_bands_info<T: GdalType + Copy + Send, BM: Sync>(
+    bands_info: &[&BandInfo<BM>],
+    offset: (isize, isize),
+    window: (usize, usize),
+) -> Result<Array3<T>> {
+    read_bands_info_with_progress(bands_info, offset, window, &|_, _| {})
+}
+
+/// Like [`read_bands_info`], calling `progress(completed_bands, total_bands)`
+/// from the reading thread as each band gets read.
+fn read_bands_info_with_progress<T: GdalType + Copy + Send, BM: Sync>(
+    bands_info: &[&BandInfo<BM>],
+    offset: (isize, isize),
+    window: (usize, usize),
+    progress: &(dyn Fn(usize, usize) + Sync),
+) -> Result<Array3<T>> {
+    let grid = highest_resolution_transform(bands_info);
+    let grid_crs = grid_crs(bands_info, grid);
+    let completed = AtomicUsize::new(0);
+    let band_rasters = bands_info
+        .par_iter()
+        .map(|band_info| {
+            let band_raster = read_band_raster(band_info, grid, grid_crs, offset, window)?;
+            progress(completed.fetch_add(1, Ordering::Relaxed) + 1, bands_info.len());
+            Ok(band_raster)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let shifts = band_rasters
+        .iter()
+        .map(|(_, transform)| grid_shift(transform))
+        .collect::<Vec<_>>();
+    Ok(Array3::from_shape_fn(
+        (band_rasters.len(), window.0, window.1),
+        |(c, x, y)| {
+            let (band_raster, transform) = &band_rasters[c];
+            pixel_on_grid(band_raster, shifts[c], x, y).unwrap_or_else(|| {
+                let point = transform.transform_point(&PixelAnchor::default().point(x, y));
+                sample_nearest(band_raster, point)
+            })
+        },
+    ))
+}
+
+/// Read `bands_info` over `window` of the grid with geo transform `grid`,
+/// resampling every band onto it at `anchor` of the grid's pixels.
+fn read_bands_info_on_grid<BM: Sync>(
+    bands_info: &[&BandInfo<BM>],
+    grid: PixelTransform,
+    offset: (isize, isize),
+    window: (usize, usize),
+    resampling: Resampling,
+    anchor: PixelAnchor,
+) -> Result<Array3<u16>> {
+    let grid_crs = grid_crs(bands_info, grid);
+    let band_rasters = bands_info
+        .par_iter()
+        .map(|band_info| {
+            let no_data_value = band_info.no_data_value().map(|value| value as u16);
+            read_band_raster(band_info, grid, grid_crs, offset, window)
+                .map(|(array, transform)| Some((array, transform, no_data_value)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(resample_band_rasters(&band_rasters, window, resampling, anchor))
+}
+
+/// [`read_band_raster`] output with the band's no data value.
+type BandRaster = (Array2<u16>, PixelTransform, Option<u16>);
+
+/// Band read over the part of its grid under a window of a read grid, with
+/// the transform from pixels of the window to pixels of the read. Bands in
+/// another projection than `grid_crs` get warped onto the window.
+fn read_band_raster<T: GdalType + Copy, BM>(
+    band_info: &BandInfo<BM>,
+    grid: PixelTransform,
+    grid_crs: &str,
+    offset: (isize, isize),
+    window: (usize, usize),
+) -> Result<(Array2<T>, PixelTransform)> {
+    let inverse_band_transform = inverse_band_transform(band_info)?;
+    if band_info.crs() != grid_crs
+        && SpatialRef::from_wkt(band_info.crs())? != SpatialRef::from_wkt(grid_crs)?
+    {
+        let window_grid = window_transform(grid, offset);
+        return warp_band(band_info, inverse_band_transform, window_grid, grid_crs, window)
+            .map(|array| (array, PixelTransform::identity()));
+    }
+    let transform = inverse_band_transform * grid;
+    let (cor_off, cor_size) =
+        transform_window((offset, window), transform, band_info.raster_size());
+    read_band(band_info, cor_off, cor_size).map(|array| (array, transform))
+}
+
+/// `band_info` bilinearly warped onto a `window` pixels grid with geo
+/// transform `transform` in projection `crs`, 0 where the band doesn't reach.
+fn warp_band<T: GdalType + Copy, BM>(
+    band_info: &BandInfo<BM>,
+    inverse_band_transform: PixelTransform,
+    transform: PixelTransform,
+    crs: &str,
+    window: (usize, usize),
+) -> Result<Array2<T>> {
+    let driver = DriverManager::get_driver_by_name("MEM")?;
+    let mut warped = driver.create_with_band_type::<T, _>("", window.0, window.1, 1)?;
+    warped.set_geo_transform(&transform_to_gdal(&transform))?;
+    warped.set_projection(crs)?;
+    if let Some((source_offset, source_window)) =
+        covering_window(band_info, inverse_band_transform, transform, window, crs)?
+    {
+        let array = read_band::<T, _>(band_info, source_offset, source_window)?;
+        let mut source =
+            driver.create_with_band_type::<T, _>("", source_window.0, source_window.1, 1)?;
+        let source_transform = window_transform(band_info.geo_transform(), source_offset);
+        source.set_geo_transform(&transform_to_gdal(&source_transform))?;
+        source.set_projection(band_info.crs())?;
+        // Arrays are indexed `[[x, y]]`, GDAL buffers are row-major.
+        let mut buffer = Buffer::new(source_window, array.t().iter().copied().collect());
+        source.rasterband(1)?.write((0, 0), source_window, &mut buffer)?;
+        reproject(&source, &warped, DEFAULT_WARP_MEM_LIMIT_MB)?;
+    }
+    let buffer = warped.rasterband(1)?.read_as::<T>((0, 0), window, window, None)?;
+    let data = buffer.into_shape_and_vec().1;
+    Ok(Array2::from_shape_fn(window, |(x, y)| data[y * window.0 + x]))
+}
+
+/// Projection of the band of `bands_info` on `grid`, or of the first one if
+/// the grid is none of theirs.
+fn grid_crs<'a, BM>(bands_info: &[&'a BandInfo<BM>], grid: PixelTransform) -> &'a str {
+    bands_info
+        .iter()
+        .find(|band_info| band_info.geo_transform() == grid)
+        .unwrap_or(&bands_info[0])
+        .crs()
+}
+
+/// Sample `band_rasters` onto `window` of the read grid, leaving the bands
+/// that couldn't be read at 0.
+fn resample_band_rasters(
+    band_rasters: &[Option<BandRaster>],
+    window: (usize, usize),
+    resampling: Resampling,
+    anchor: PixelAnchor,
+) -> Array3<u16> {
+    let shifts = band_rasters
+        .iter()
+        .map(|band_raster| band_raster.as_ref().and_then(|(_, transform, _)| grid_shift(transform)))
+        .collect::<Vec<_>>();
+    Array3::from_shape_fn(
+        (band_rasters.len(), window.0, window.1),
+        |(c, x, y)| {
+            let Some((band_raster, transform, no_data_value)) = &band_rasters[c] else {
+                return 0;
+            };
+            if let Some(value) = pixel_on_grid(band_raster, shifts[c], x, y) {
+                return value;
+            }
+            let corrected_coords = transform.transform_point(&anchor.point(x, y));
+            match resampling {
+                Resampling::Nearest => sample_nearest(band_raster, corrected_coords),
+                Resampling::Bilinear => {
+                    sample_bilinear(band_raster, corrected_coords, *no_data_value)
+                }
+            }
+        },
+    )
+}
+
+/// Pixel `(x, y)` of the read grid of a band on it, `shift` pixels off the
+/// band's array, see [`grid_shift`]. Bands on the read grid come out as read,
+/// whatever the sampling: float sampling points off by rounding errors could
+/// fall in the wrong pixel at corners, or get a bit of a neighbour's value.
+fn pixel_on_grid<T: Copy>(
+    band_raster: &Array2<T>,
+    shift: Option<(isize, isize)>,
+    x: usize,
+    y: usize,
+) -> Option<T> {
+    let (dx, dy) = shift?;
+    band_raster.get([x.checked_add_signed(dx)?, y.checked_add_signed(dy)?]).copied()
+}
+
+/// Whole pixel shift `transform` amounts to, within
+/// `BAND_ALIGNMENT_TOLERANCE`, if it maps pixels of the read grid one to one
+/// onto those of a band.
+fn grid_shift(transform: &PixelTransform) -> Option<(isize, isize)> {
+    let shift = (transform.m13.round(), transform.m23.round());
+    ((transform.fixed_view::<2, 2>(0, 0) - Matrix2::identity()).amax()
+        <= BAND_ALIGNMENT_TOLERANCE
+        && (transform.m13 - shift.0).abs() <= BAND_ALIGNMENT_TOLERANCE
+        && (transform.m23 - shift.1).abs() <= BAND_ALIGNMENT_TOLERANCE)
+        .then_some((shift.0 as isize, shift.1 as isize))
+}
+
+/// Shape of a `size` pixels raster scaled down, if need be, to fit within
+/// `max_dim` pixels.
+fn fit_within(size: (usize, usize), max_dim: usize) -> (usize, usize) {
+    let scale = (max_dim as f64 / size.0.max(size.1) as f64).min(1.);
+    (
+        ((size.0 as f64 * scale).round() as usize).max(1),
+        ((size.1 as f64 * scale).round() as usize).max(1),
+    )
+}
+
+/// Count, mean, sum of squared deviations, min and max of a stream of values,
+/// updated as in Welford's algorithm and merged as in Chan et al.'s.
+#[derive(Debug, Clone, Copy)]
+struct RunningStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self { count: 0, mean: 0., m2: 0., min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+}
+
+impl RunningStats {
+    fn push(self, value: f64) -> Self {
+        let count = self.count + 1;
+        let delta = value - self.mean;
+        let mean = self.mean + delta / count as f64;
+        Self {
+            count,
+            mean,
+            m2: self.m2 + delta * (value - mean),
+            min: self.min.min(value),
+            max: self.max.max(value),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        let count = self.count + other.count;
+        if count == 0 {
+            return self;
+        }
+        let delta = other.mean - self.mean;
+        let weight = other.count as f64 / count as f64;
+        Self {
+            count,
+            mean: self.mean + delta * weight,
+            m2: self.m2 + other.m2 + delta * delta * self.count as f64 * weight,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Pixel of `band_raster` holding `point`, in pixel coordinates. Rounding can
+/// land a pixel past the band's edge, which is also the edge of the read.
+fn sample_nearest<T: Copy>(band_raster: &Array2<T>, point: Point2<f64>) -> T {
+    let (width, height) = band_raster.dim();
+    let clamp = |coordinate: f64, extent: usize| {
+        (coordinate.max(0.) as usize).min(extent.saturating_sub(1))
+    };
+    band_raster[[clamp(point.x, width), clamp(point.y, height)]]
+}
+
+/// Bilinear interpolation of `band_raster` at `point`, in pixel coordinates
+/// where pixel centres lie at half integers. No data pixels are left out and
+/// the weights of the others renormalized, so the result is only no data if
+/// all four neighbours are.
+pub(crate) fn sample_bilinear(
+    band_raster: &Array2<u16>,
+    point: Point2<f64>,
+    no_data_value: Option<u16>,
+) -> u16 {
+    let (width, height) = band_raster.dim();
+    let x = (point.x - 0.5).clamp(0., (width - 1) as f64);
+    let y = (point.y - 0.5).clamp(0., (height - 1) as f64);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+    let neighbours = [
+        (band_raster[[x0, y0]], (1. - fx) * (1. - fy)),
+        (band_raster[[x1, y0]], fx * (1. - fy)),
+        (band_raster[[x0, y1]], (1. - fx) * fy),
+        (band_raster[[x1, y1]], fx * fy),
+    ];
+    let valid = || {
+        neighbours
+            .into_iter()
+            .filter(|&(value, _)| Some(value) != no_data_value)
+    };
+    let (sum, sum_weights) = valid().fold((0., 0.), |(sum, sum_weights), (value, weight)| {
+        (sum + value as f64 * weight, sum_weights + weight)
+    });
+    if sum_weights > f64::EPSILON {
+        (sum / sum_weights).round() as u16
+    } else {
+        // Only valid neighbours without weight, if any, take the first of them.
+        valid()
+            .next()
+            .map_or(no_data_value.unwrap_or_default(), |(value, _)| value)
+    }
+}
+
+/// Bilinearly warp `src` onto `dst`, like `gdal::raster::reproject` but with
+/// the warper's memory limited to `warp_mem_limit_mb` megabytes.
+fn reproject(src: &Dataset, dst: &Dataset, warp_mem_limit_mb: usize) -> Result<()> {
+    let warp_memory_limit = (warp_mem_limit_mb * 1024 * 1024) as f64;
+    let rv = unsafe {
+        gdal_sys::GDALReprojectImage(
+            src.c_dataset(),
+            ptr::null(),
+            dst.c_dataset(),
+            ptr::null(),
+            gdal_sys::GDALResampleAlg::GRA_Bilinear,
+            warp_memory_limit,
+            0.,
+            None,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    if rv != gdal_sys::CPLErr::CE_None {
+        let msg = unsafe { CStr::from_ptr(gdal_sys::CPLGetLastErrorMsg()) };
+        let error = GdalError::CplError {
+            class: rv,
+            number: unsafe { gdal_sys::CPLGetLastErrorNo() },
+            msg: msg.to_string_lossy().into_owned(),
+        };
+        unsafe { gdal_sys::CPLErrorReset() };
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// Windows of `tile` sized tiles covering a grid of `size` pixels, see
+/// [`Raster::tiles`].
+pub(crate) fn tile_windows(
+    size: (usize, usize),
+    tile: (usize, usize),
+) -> impl Iterator<Item = RasterWindow> {
+    assert!(tile.0 > 0 && tile.1 > 0, "tile size has a zero: {tile:?}");
+    (0..size.1).step_by(tile.1).flat_map(move |y| {
+        (0..size.0).step_by(tile.0).map(move |x| {
+            let window = (tile.0.min(size.0 - x), tile.1.min(size.1 - y));
+            ((x as isize, y as isize), window)
+        })
+    })
+}
+
+/// Set the pixels of `array` whose centre is more than `radius` pixels away
+/// from `center` to `NaN`.
+pub(crate) fn mask_outside_circle(array: &mut Array3<f32>, center: Point2<f64>, radius: f64) {
+    for mut band in array.outer_iter_mut() {
+        band.indexed_iter_mut()
+            .filter(|((x, y), _)| {
+                let pixel_centre = Point2::new(*x as f64 + 0.5, *y as f64 + 0.5);
+                nalgebra::distance(&pixel_centre, &center) > radius
+            })
+            .for_each(|(_, value)| *value = f32::NAN);
+    }
+}
+
+/// Smallest window of `array` holding all of its pixels other than
+/// `no_data_value`, `None` if there are none.
+pub(crate) fn valid_window(array: ArrayView2<u16>, no_data_value: u16) -> Option<RasterWindow> {
+    let (xs, ys): (Vec<usize>, Vec<usize>) = array
+        .indexed_iter()
+        .filter(|&(_, &value)| value != no_data_value)
+        .map(|(index, _)| index)
+        .unzip();
+    let (min_x, max_x) = xs.into_iter().minmax().into_option()?;
+    let (min_y, max_y) = ys.into_iter().minmax().into_option()?;
+    Some((
+        (min_x as isize, min_y as isize),
+        (max_x - min_x + 1, max_y - min_y + 1),
+    ))
+}
+
+/// Patches of `array`, see `Raster::read_patches`.
+pub(crate) fn split_patches(
+    array: &Array3<u16>,
+    patch: (usize, usize),
+    stride: (usize, usize),
+    edges: EdgePatches,
+) -> Vec<Array3<u16>> {
+    let (bands, width, height) = array.dim();
+    let origins = |extent: usize, patch: usize, stride: usize| {
+        (0..extent)
+            .step_by(stride)
+            .take_while(|&origin| match edges {
+                EdgePatches::Drop => origin + patch <= extent,
+                // Until the previous patch reaches the edge.
+                EdgePatches::Pad => origin == 0 || origin - stride + patch < extent,
+            })
+            .collect::<Vec<_>>()
+    };
+    origins(height, patch.1, stride.1)
+        .into_iter()
+        .cartesian_product(origins(width, patch.0, stride.0))
+        .map(|(y, x)| {
+            let (x_end, y_end) = ((x + patch.0).min(width), (y + patch.1).min(height));
+            let mut patch_array = Array3::zeros((bands, patch.0, patch.1));
+            patch_array
+                .slice_mut(s![.., ..x_end - x, ..y_end - y])
+                .assign(&array.slice(s![.., x..x_end, y..y_end]));
+            patch_array
+        })
+        .collect()
+}
+
+/// 64 bit FNV-1a hash of `bytes`.
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes
+        .into_iter()
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// Little endian bytes of `array`, see `Raster::read_bands_le_bytes`.
+pub(crate) fn to_le_bytes(array: &Array3<u16>) -> Vec<u8> {
+    array
+        .view()
+        .permuted_axes([0, 2, 1])
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Run `read` on a worker thread, returning `ReadTimeout` if it doesn't
+/// finish within `timeout`. A panic in `read` is propagated to the caller.
+pub(crate) fn run_with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    read: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let (sender, receiver) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        // Past the deadline nobody listens anymore, the result is dropped.
+        let _ = sender.send(read());
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(Sentinel2ArrayError::ReadTimeout(timeout)),
+        Err(RecvTimeoutError::Disconnected) => panic::resume_unwind(worker.join().unwrap_err()),
+    }
+}
+
+/// Parse zone and hemisphere from a CRS name like `WGS 84 / UTM zone 33N`.
+pub(crate) fn utm_zone_from_name(name: &str) -> Option<(u8, Hemisphere)> {
+    let (_, zone) = name.rsplit_once("UTM zone ")?;
+    let (number, hemisphere) = if let Some(number) = zone.strip_suffix('N') {
+        (number, Hemisphere::North)
+    } else {
+        (zone.strip_suffix('S')?, Hemisphere::South)
+    };
+    number
+        .parse()
+        .ok()
+        .filter(|number| (1..=60).contains(number))
+        .map(|number| (number, hemisphere))
 }
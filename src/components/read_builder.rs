@@ -0,0 +1,221 @@
+use ndarray::Array3;
+use rasters::prelude::{PixelTransform, RasterWindow};
+
+use super::{
+    converter::PixelConverter,
+    raster::{window_transform, PixelAnchor, Raster, Resampling},
+    Result, Sentinel2ArrayError,
+};
+use crate::sensors::Sensor;
+
+/// Output of [`ReadBuilder::execute_georef`] and its variants.
+#[derive(Debug, Clone)]
+pub struct ReadResult<T> {
+    /// Stack of the bands, in requested order.
+    pub array: Array3<T>,
+    pub band_names: Vec<String>,
+    /// Maps pixel coordinates of `array` to map coordinates.
+    pub transform: PixelTransform,
+}
+
+/// Bounding box of a read, see [`Raster::read_bands_bbox`].
+#[derive(Debug, Clone)]
+struct Bbox {
+    min: (f64, f64),
+    max: (f64, f64),
+    crs: Option<String>,
+}
+
+/// Configures a read of a [`Raster`], obtained through [`Raster::read`].
+#[derive(Debug)]
+pub struct ReadBuilder<'a, S: Sensor> {
+    raster: &'a Raster<S>,
+    band_names: Vec<String>,
+    offset: (isize, isize),
+    window: (usize, usize),
+    full_extent: bool,
+    bbox: Option<Bbox>,
+    resampling: Resampling,
+    anchor: PixelAnchor,
+    fill: Option<u16>,
+    max_read_bytes: Option<usize>,
+}
+
+impl<'a, S: Sensor> ReadBuilder<'a, S> {
+    pub fn new(raster: &'a Raster<S>) -> Self {
+        Self {
+            raster,
+            band_names: Vec::new(),
+            offset: (0, 0),
+            window: (0, 0),
+            full_extent: false,
+            bbox: None,
+            resampling: Resampling::default(),
+            anchor: PixelAnchor::default(),
+            fill: None,
+            max_read_bytes: None,
+        }
+    }
+
+    /// Raster the read is of.
+    pub fn raster(&self) -> &'a Raster<S> {
+        self.raster
+    }
+
+    pub fn bands<N: AsRef<str>>(mut self, band_names: impl IntoIterator<Item = N>) -> Self {
+        self.band_names = band_names
+            .into_iter()
+            .map(|band_name| band_name.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    pub fn window(mut self, offset: (isize, isize), window: (usize, usize)) -> Self {
+        self.offset = offset;
+        self.window = window;
+        self.full_extent = false;
+        self.bbox = None;
+        self
+    }
+
+    /// Read the whole extent of the raster instead of a window, in strips.
+    pub fn full_extent(mut self) -> Self {
+        self.full_extent = true;
+        self.bbox = None;
+        self
+    }
+
+    /// Read the window covering the bounding box from `min` to `max` instead,
+    /// in projection `crs` or in the raster's one if `None`, see
+    /// [`Raster::read_bands_bbox`].
+    pub fn bbox(mut self, min: (f64, f64), max: (f64, f64), crs: Option<&str>) -> Self {
+        self.bbox = Some(Bbox {
+            min,
+            max,
+            crs: crs.map(String::from),
+        });
+        self.full_extent = false;
+        self
+    }
+
+    pub fn resampling(mut self, resampling: Resampling) -> Self {
+        self.resampling = resampling;
+        self
+    }
+
+    /// Sample coarser bands at `anchor` of the read pixels, their centres by
+    /// default.
+    pub fn pixel_anchor(mut self, anchor: PixelAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Set the pixels of the window past the bands to `fill`, instead of
+    /// filling them in from the nearest edge pixel.
+    pub fn fill(mut self, fill: u16) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    /// Refuse reads whose output would take more than `limit` bytes.
+    pub fn max_read_bytes(mut self, limit: usize) -> Self {
+        self.max_read_bytes = Some(limit);
+        self
+    }
+
+    pub fn execute(self) -> Result<Array3<u16>> {
+        self.read_window().map(|(array, _)| array)
+    }
+
+    /// Like `execute`, along with the band names and the geo transform of
+    /// the read.
+    pub fn execute_georef(self) -> Result<ReadResult<u16>> {
+        let raster = self.raster;
+        let grid = if self.full_extent {
+            raster.grid()?.transform
+        } else {
+            raster.highest_resolution_transform(&self.band_name_refs())?
+        };
+        let band_names = self.band_names.clone();
+        let (array, offset) = self.read_window()?;
+        Ok(ReadResult {
+            array,
+            band_names,
+            transform: window_transform(grid, offset),
+        })
+    }
+
+    /// Like `execute_georef`, with the digital numbers converted by
+    /// `converter`.
+    pub fn execute_converted(self, converter: &impl PixelConverter) -> Result<ReadResult<f32>> {
+        let ReadResult {
+            array,
+            band_names,
+            transform,
+        } = self.execute_georef()?;
+        let converted = Array3::from_shape_fn(array.dim(), |(c, x, y)| {
+            converter.convert(&band_names[c], array[[c, x, y]])
+        });
+        Ok(ReadResult {
+            array: converted,
+            band_names,
+            transform,
+        })
+    }
+
+    /// Window the read is over, `None` for the whole extent.
+    fn resolve_window(&self) -> Result<Option<RasterWindow>> {
+        if self.full_extent {
+            return Ok(None);
+        }
+        match &self.bbox {
+            Some(Bbox { min, max, crs }) => self
+                .raster
+                .bbox_window(&self.band_name_refs(), *min, *max, crs.as_deref())
+                .map(Some),
+            None => Ok(Some((self.offset, self.window))),
+        }
+    }
+
+    fn band_name_refs(&self) -> Vec<&str> {
+        self.band_names.iter().map(String::as_str).collect()
+    }
+
+    /// The read stack, with the offset it was read at.
+    fn read_window(self) -> Result<(Array3<u16>, (isize, isize))> {
+        let resolved = self.resolve_window()?;
+        let band_names = self.band_name_refs();
+        if let Some(limit) = self.max_read_bytes {
+            let window = match resolved {
+                Some((_, window)) => window,
+                None => self.raster.size()?,
+            };
+            let requested = self.raster.estimate_read_bytes(&band_names, window);
+            if requested > limit {
+                return Err(Sentinel2ArrayError::ReadTooLarge { requested, limit });
+            }
+        }
+        let Some((offset, window)) = resolved else {
+            return self
+                .raster
+                .read_full_chunked(&band_names, self.resampling, self.anchor)
+                .map(|array| (array, (0, 0)));
+        };
+        let Some(fill) = self.fill else {
+            return self
+                .raster
+                .read_bands_anchored(&band_names, offset, window, self.resampling, self.anchor)
+                .map(|array| (array, offset));
+        };
+        // The window may reach past the bands, whose pixels there get filled.
+        let mut array = self.raster.read_bands_anchored_unchecked(
+            &band_names,
+            offset,
+            window,
+            self.resampling,
+            self.anchor,
+        )?;
+        self.raster.fill_outside(&band_names, offset, &mut array, fill)?;
+        Ok((array, offset))
+    }
+}
@@ -1,9 +1,14 @@
 use super::{band::Bands, raster::Raster, Result, Sentinel2ArrayError};
 use crate::sensors::Sensor;
 use gdal;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub trait DatasetReader: Sensor + Sized {
+    /// Open the product at `path`, which may be any name GDAL opens, including
+    /// virtual file system ones like `/vsizip/`, `/vsicurl/` or `/vsis3/`.
+    /// Those are configured through GDAL's environment variables, e.g.
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, `AWS_REGION` or
+    /// `AWS_NO_SIGN_REQUEST` for S3, and `GDAL_HTTP_*` ones for HTTP.
     fn raster_from<P: AsRef<Path>>(path: P) -> Result<Raster<Self>> {
         let dataset = Self::open_dataset(path)?;
         Self::read_dataset(dataset)
@@ -11,7 +16,20 @@ pub trait DatasetReader: Sensor + Sized {
     }
 
     fn open_dataset<P: AsRef<Path>>(path: P) -> Result<gdal::Dataset> {
-        let dataset = gdal::Dataset::open(path)?;
+        Self::open_dataset_with_options(path, &[])
+    }
+
+    /// Like `open_dataset`, passing GDAL open options like `KEY=VALUE` to the
+    /// driver.
+    fn open_dataset_with_options<P: AsRef<Path>>(
+        path: P,
+        open_options: &[&str],
+    ) -> Result<gdal::Dataset> {
+        let options = gdal::DatasetOptions {
+            open_options: Some(open_options),
+            ..Default::default()
+        };
+        let dataset = gdal::Dataset::open_ex(Self::dataset_path(path.as_ref())?, options)?;
         let dataset_driver = dataset.driver().short_name();
         if dataset_driver.eq_ignore_ascii_case(Self::GDAL_DRIVER_NAME) {
             Ok(dataset)
@@ -23,6 +41,12 @@ pub trait DatasetReader: Sensor + Sized {
         }
     }
 
+    /// Name GDAL opens the product at `path` with, e.g. the metadata file of a
+    /// product given as a directory. The path itself by default.
+    fn dataset_path(path: &Path) -> Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+
     fn read_dataset(dataset: gdal::Dataset)
         -> Result<(Bands<Self::BandMetadata>, Self::RasterMetadata)>;
 }
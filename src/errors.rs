@@ -8,18 +8,104 @@ pub enum Sentinel2ArrayError {
     GdalError(#[from] GdalError),
     #[error(transparent)]
     RastersError(#[from] rasters::Error),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    ZipError(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    XmlError(#[from] roxmltree::Error),
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    ArrowError(#[from] arrow::error::ArrowError),
+    #[cfg(feature = "candle")]
+    #[error(transparent)]
+    CandleError(#[from] candle_core::Error),
+    #[cfg(feature = "polars")]
+    #[error(transparent)]
+    PolarsError(#[from] polars::error::PolarsError),
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
     /*#[error(transparent)]
     ProjError(#[from] ProjCreateError),
     #[error(transparent)]
-    ShapeError(#[from] ShapeError),
+    ShapeError(#[from] ShapeError), */
     #[error("Dataset {0} contains bands with different projections.")]
-    MultipleProjectionsInDataset(String), */
+    MultipleProjectionsInDataset(String),
+    #[error("Bands {0:?} can't be co-registered with the finest requested band.")]
+    IncompatibleBandGroups(Vec<String>),
     #[error("Band `{0}` has a non inverteble geo transform.")]
     BandTransformNotInvertible(String),
     #[error("Band `{0}` not found.")]
     BandNotFound(String),
+    #[error("No band within {tolerance} nm of {wavelength} nm.")]
+    NoBandNearWavelength { wavelength: f64, tolerance: f64 },
+    #[error("No bands found.")]
+    NoBandsFound,
+    #[error("Projection `{0}` is not a UTM projection.")]
+    NotUtmProjection(String),
+    #[error("Can't open {0} for update to build overviews into, e.g. as it's in a zip archive.")]
+    OverviewsNotWritable(String),
+    #[error("No bands at {0} m resolution.")]
+    NoBandsAtResolution(u8),
+    #[error("Projection `{0}` has no EPSG code.")]
+    NoEpsgCode(String),
     #[error("Couldn't find {key} in metadata of {object_desc}.")]
     MetadataKeyNotFound { object_desc: String, key: String },
+    #[error("Couldn't parse value `{value}` of {key} in metadata of {object_desc}.")]
+    MetadataValueNotParsable { object_desc: String, key: String, value: String },
+    #[error("Read of {requested} bytes exceeds the limit of {limit} bytes.")]
+    ReadTooLarge { requested: usize, limit: usize },
+    #[error("Read didn't finish within {0:?}.")]
+    ReadTimeout(std::time::Duration),
+    #[error("Level-2A product needed for {0}.")]
+    NotLevel2A(String),
+    #[error("Couldn't find the metadata file of product directory {0}.")]
+    ProductMetadataNotFound(String),
+    #[error("Couldn't find the tile metadata of product {0}.")]
+    TileMetadataNotFound(String),
+    #[error("Couldn't parse the angle grids of `{0}` in tile metadata.")]
+    AngleGridNotParsable(String),
+    #[error("Mask `{0}` not found.")]
+    MaskNotFound(String),
+    #[error("View angle grids of band `{0}` not found.")]
+    ViewAnglesNotFound(String),
+    #[error("Bounding box from {min:?} to {max:?} lies outside of the raster.")]
+    BboxOutsideRaster { min: (f64, f64), max: (f64, f64) },
+    #[error("Window {window:?} at {offset:?} reaches past the {raster_size:?} pixels grid.")]
+    WindowOutOfBounds {
+        offset: (isize, isize),
+        window: (usize, usize),
+        raster_size: (usize, usize),
+    },
+    #[error("Window {0:?} holds no pixels.")]
+    EmptyWindow((usize, usize)),
+    #[error("Mosaic needs at least one raster.")]
+    NoRastersInMosaic,
+    #[error("Raster {0} of the mosaic isn't in the projection of the first one.")]
+    MosaicProjectionMismatch(usize),
+    #[error("Raster {0} of the mosaic isn't aligned with the pixel grid of the first one.")]
+    MosaicGridMismatch(usize),
+    #[error("{0} isn't a tiled Cloud Optimized GeoTIFF.")]
+    CogNotTiled(String),
+    #[error("Band `{band_name}` of type {data_type} can't be read as {requested} without loss.")]
+    DataTypeMismatch { band_name: String, data_type: String, requested: String },
+    #[error("Couldn't find the product {0} of the scene index.")]
+    IndexDataNotFound(String),
+    #[error("Band `{band_name}` of the scene index is in band group {group}, of {band_groups}.")]
+    InvalidIndex { band_name: String, group: usize, band_groups: usize },
+    #[error("Band `{band_name}` of the scene index is band {index} of its group, of {bands}.")]
+    InvalidIndexBand { band_name: String, index: usize, bands: usize },
+    #[error("Band `{band}` is off the grid of `{reference}`, by {offset:?} pixels.")]
+    MisalignedBands { band: String, reference: String, offset: (f64, f64) },
+    #[error("Can't standardize band `{band_name}` with mean {mean} and std {std}.")]
+    InvalidBandStats { band_name: String, mean: f32, std: f32 },
+    #[error("Geo transform and projection disagree: {0}.")]
+    GeoReferenceInconsistent(String),
+    #[error("Band `{0}` holds no valid pixels.")]
+    NoValidData(String),
+    #[error("Product failed validation: {}.", .0.join(", "))]
+    ValidationFailed(Vec<String>),
     #[error("Trying to use parser for {parser} to read {dataset} data.")]
     WrongParser { parser: String, dataset: String },
 }
@@ -2,15 +2,49 @@ mod components;
 mod errors;
 mod sensors;
 
-pub use components::reader::DatasetReader;
-pub use sensors::Sentinel2;
+pub use components::{
+    band::BandStats,
+    converter::{Identity, PixelConverter},
+    mosaic::{Mosaic, MOSAIC_NODATA},
+    reader::DatasetReader,
+};
+pub use sensors::{
+    Acquisition, ClassResample, ProductLevel, Radiance, RasterOptions, RasterSummary,
+    Reflectance, S2Band, Sentinel2, Stretch,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use components::{reader::DatasetReader, raster::Raster};
+    use components::{
+        band::{BandGroup, BandInfo, Bands},
+        converter::{Identity, PixelConverter},
+        metadata::{Metadata, MetadataDelta},
+        reader::DatasetReader,
+        raster::{
+            clip_window, fnv1a, mask_outside_circle, run_with_timeout, sample_bilinear,
+            split_patches, tile_windows, to_le_bytes, utm_zone_from_name, valid_window,
+            BandMismatch, EdgePatches, Grid, Hemisphere, PartialPolicy, PartialRead, PixelAnchor,
+            PixelStatus, Raster, Resampling,
+        },
+        Sentinel2ArrayError,
+    };
+    use gdal::Metadata as GdalMetadata;
+    use nalgebra::Point2;
+    use rasters::reader::ChunkReader;
+    use ndarray::{s, Array2, Axis};
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Arc,
+        thread,
+        time::Duration,
+    };
     use rstest::{fixture, rstest};
-    use sensors::Sentinel2;
+    use sensors::{
+        sentinel2::{self, count_clear, dilate, resample_classes, Subdataset},
+        Sensor, Sentinel2,
+    };
 
     const TEST_DATA: &str =
         "data/S2B_MSIL2A_20241126T093239_N0511_R136_T33PTM_20241126T120342.SAFE.zip";
@@ -20,11 +54,2672 @@ mod tests {
         Sentinel2::raster_from(TEST_DATA).unwrap()
     }
 
+    /// Sensor of the rasters tests build out of synthetic GeoTIFFs.
+    #[derive(Debug)]
+    struct Reference;
+    impl Sensor for Reference {
+        type RasterMetadata = ();
+        type BandMetadata = ();
+        const GDAL_DRIVER_NAME: &'static str = "GTiff";
+    }
+
+    /// GeoTIFF of `bands` zeroed `u16` bands of `size` pixels in UTM zone 33N,
+    /// at `rusterio_{name}.tif` of the temporary directory.
+    fn gtiff_dataset(
+        name: &str,
+        geo_transform: [f64; 6],
+        size: (usize, usize),
+        bands: usize,
+    ) -> (gdal::Dataset, PathBuf) {
+        let path = std::env::temp_dir().join(format!("rusterio_{name}.tif"));
+        let mut dataset = gdal::DriverManager::get_driver_by_name("GTiff")
+            .unwrap()
+            .create_with_band_type::<u16, _>(&path, size.0, size.1, bands)
+            .unwrap();
+        dataset.set_geo_transform(&geo_transform).unwrap();
+        let utm = gdal::spatial_ref::SpatialRef::from_epsg(32633).unwrap();
+        dataset.set_projection(&utm.to_wkt().unwrap()).unwrap();
+        (dataset, path)
+    }
+
+    /// Band group of a `gtiff_dataset`, with the path of its file to remove.
+    fn gtiff_band_group(
+        name: &str,
+        geo_transform: [f64; 6],
+        size: (usize, usize),
+        bands: usize,
+    ) -> (Arc<BandGroup>, PathBuf) {
+        let (dataset, path) = gtiff_dataset(name, geo_transform, size, bands);
+        (Arc::new(BandGroup::new(&dataset).unwrap()), path)
+    }
+
     #[rstest]
     fn play_ground(test_raster: Raster<Sentinel2>) {
         println!("{:#?}", test_raster.read_bands(vec!["B4", "B2", "B3"], (0, 0), (125, 125)).unwrap().dim())
     }
 
+    #[cfg(feature = "mmap")]
+    #[rstest]
+    fn read_band_mmap(test_raster: Raster<Sentinel2>) {
+        let out_path = std::env::temp_dir().join("rusterio_read_band_mmap_B4.bin");
+        let mmap = test_raster.read_band_mmap("B4", &out_path).unwrap();
+        let width = 10980;
+        for (x, y) in [(0, 0), (17, 3), (500, 1200), (10979, 10979)] {
+            let offset = (y * width + x) * 2;
+            let mapped = u16::from_ne_bytes([mmap[offset], mmap[offset + 1]]);
+            let direct = test_raster
+                .read_bands(vec!["B4"], (x as isize, y as isize), (1, 1))
+                .unwrap()[[0, 0, 0]];
+            assert_eq!(mapped, direct);
+        }
+        drop(mmap);
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[rstest]
+    fn read_bands_topo_corrected_flat_terrain(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((2000, 2000), (64, 64));
+        // A flat 20 m DEM covering the window with a 200 m margin.
+        let [x, _, _, y, _, _] = test_raster.band_geo_transform("B4").unwrap();
+        let geo_transform = [x + 19800., 20., 0., y - 19800., 0., -20.];
+        let (mut dataset, path) = gtiff_dataset("flat_dem", geo_transform, (54, 54), 1);
+        dataset.set_projection(&test_raster.profile().unwrap().crs).unwrap();
+        dataset.rasterband(1).unwrap().fill(250., None).unwrap();
+        let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+        drop(dataset);
+        let flat_dem = Raster::<Reference>::new(
+            Bands::from_iter([("DEM".to_string(), BandInfo::new(band_group, 1, ()))]),
+            (),
+        );
+
+        let corrected = test_raster
+            .read_bands_topo_corrected(vec!["B4", "B8"], offset, window, &flat_dem)
+            .unwrap();
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B4", "B8"], offset, window)
+            .unwrap();
+        assert!(corrected
+            .iter()
+            .zip(reflectance.iter())
+            .all(|(corrected, reflectance)| (corrected - reflectance).abs() < 1e-5));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn utm_zone(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.utm_zone().unwrap(), (33, Hemisphere::North));
+        assert_eq!(
+            utm_zone_from_name("WGS 84 / UTM zone 56S"),
+            Some((56, Hemisphere::South))
+        );
+        // Names ending in a multibyte character.
+        assert_eq!(utm_zone_from_name("WGS 84 / UTM zone 33é"), None);
+        assert_eq!(utm_zone_from_name("UTM zone é"), None);
+    }
+
+    #[rstest]
+    fn read_bands_timeout(test_raster: Raster<Sentinel2>) {
+        let timed = test_raster
+            .read_bands_timeout(vec!["B4", "B11"], (0, 0), (64, 64), Duration::from_secs(60))
+            .unwrap();
+        let direct = test_raster.read_bands(vec!["B4", "B11"], (0, 0), (64, 64)).unwrap();
+        assert_eq!(timed, direct);
+    }
+
+    #[test]
+    fn read_timeout_on_slow_band() {
+        let slow_band_read = || {
+            thread::sleep(Duration::from_secs(2));
+            Ok(Array2::<u16>::zeros((8, 8)))
+        };
+        assert!(matches!(
+            run_with_timeout(Duration::from_millis(50), slow_band_read),
+            Err(Sentinel2ArrayError::ReadTimeout(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_bands_timeout_on_slow_band() {
+        // Reads reopen the band's file, swapped for a FIFO that blocks them
+        // until a writer shows up.
+        let (dataset, path) = gtiff_dataset("slow_band", [0., 10., 0., 0., 0., -10.], (8, 8), 1);
+        let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+        drop(dataset);
+        std::fs::remove_file(&path).unwrap();
+        assert!(std::process::Command::new("mkfifo").arg(&path).status().unwrap().success());
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([("B1".to_string(), BandInfo::new(band_group, 1, ()))]),
+            (),
+        );
+
+        let timeout = Duration::from_millis(200);
+        assert!(matches!(
+            raster.read_bands_timeout(["B1"], (0, 0), (8, 8), timeout),
+            Err(Sentinel2ArrayError::ReadTimeout(elapsed)) if elapsed == timeout
+        ));
+        // Let the abandoned read fail on the empty FIFO.
+        drop(std::fs::OpenOptions::new().write(true).open(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn read_bands_present(test_raster: Raster<Sentinel2>) {
+        let (array, absent) = test_raster
+            .read_bands_present(vec!["B4", "B99", "B3", "XX"], (0, 0), (32, 32))
+            .unwrap();
+        assert_eq!(array.dim(), (2, 32, 32));
+        assert_eq!(absent, vec!["B99", "XX"]);
+        assert_eq!(array, test_raster.read_bands(vec!["B4", "B3"], (0, 0), (32, 32)).unwrap());
+        // Hanging past the right edge of the scene.
+        assert!(matches!(
+            test_raster.read_bands_present(vec!["B4", "B99"], (10970, 0), (32, 32)),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_masked(test_raster: Raster<Sentinel2>) {
+        // Hanging 10 pixels past the right edge of the scene.
+        let (offset, window) = ((10970, 5000), (20, 8));
+        let (array, valid) = test_raster
+            .read_bands_masked(vec!["B4", "B11"], offset, window)
+            .unwrap();
+        assert_eq!(array.dim(), valid.dim());
+        for ((c, x, y), &valid) in valid.indexed_iter() {
+            let no_data_value = test_raster.band_no_data_value(["B4", "B11"][c]).unwrap();
+            let expected = x < 10 && no_data_value != Some(array[[c, x, y]] as f64);
+            assert_eq!(valid, expected);
+        }
+    }
+
+    #[rstest]
+    fn read_scl(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((1000, 2000), (30, 20));
+        let scl = test_raster.read_scl(offset, window).unwrap();
+        assert_eq!(scl.dim(), window);
+        // Every 20 m pixel covers 2x2 pixels of the 10 m grid.
+        let native = test_raster.read_band_source_window("SCL", (500, 1000), (15, 10)).unwrap();
+        assert_eq!(scl.slice(ndarray::s![..;2, ..;2]), native.mapv(|class| class as u8));
+        assert!(scl.iter().all(|&class| class <= 11));
+        assert!(matches!(
+            test_raster.read_scl((10970, 2000), window),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_tiled(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((101, 57), (200, 333));
+        let tiled = Sentinel2::raster_from(TEST_DATA).unwrap().with_read_tile_pixels(10_000);
+        assert_eq!(
+            tiled.read_bands(vec!["B4", "B11", "B1"], offset, window).unwrap(),
+            test_raster.read_bands(vec!["B4", "B11", "B1"], offset, window).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn read_bands_tiled_large(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((0, 0), (4000, 3000));
+        let untiled = test_raster.clone().with_read_tile_pixels(usize::MAX);
+        let start = std::time::Instant::now();
+        let whole = untiled.read_bands(vec!["B4", "B11"], offset, window).unwrap();
+        let untiled_time = start.elapsed();
+        let tiled = test_raster.with_read_tile_pixels(1_000_000);
+        let start = std::time::Instant::now();
+        let strips = tiled.read_bands(vec!["B4", "B11"], offset, window).unwrap();
+        let tiled_time = start.elapsed();
+        assert_eq!(to_le_bytes(&strips), to_le_bytes(&whole));
+        // Loose enough for noisy machines, strips shouldn't cost much more.
+        assert!(tiled_time < untiled_time * 4, "{tiled_time:?} vs {untiled_time:?}");
+    }
+
+    #[rstest]
+    fn self_check_georef(test_raster: Raster<Sentinel2>) {
+        test_raster.self_check_georef().unwrap();
+
+        // UTM coordinates way past the poles.
+        let (band_group, path) =
+            gtiff_band_group("self_check_georef", [5e7, 10., 0., 5e7, 0., -10.], (10, 10), 1);
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([("B1".to_string(), BandInfo::new(band_group, 1, ()))]),
+            (),
+        );
+        assert!(matches!(
+            raster.self_check_georef(),
+            Err(Sentinel2ArrayError::GeoReferenceInconsistent(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn band_stats(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((4000, 4000), (50, 40));
+        let stats = test_raster.band_stats("B8", offset, window).unwrap();
+        let band = test_raster.read_band("B8", offset, window).unwrap().mapv(f64::from);
+        assert!((stats.mean - band.mean().unwrap()).abs() < 1e-9);
+        assert!((stats.std_dev - band.std(0.)).abs() < 1e-9);
+        assert_eq!(stats.min, band.fold(f64::INFINITY, |min, &value| min.min(value)));
+        assert_eq!(stats.max, band.fold(0f64, |max, &value| max.max(value)));
+    }
+
+    #[rstest]
+    fn band_histogram(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((4000, 4000), (50, 40));
+        let histogram = test_raster.band_histogram("B8", offset, window, 16, None).unwrap();
+        assert_eq!(histogram.len(), 16);
+        let no_data_value = test_raster.band_no_data_value("B8").unwrap().map(|v| v as u16);
+        let band = test_raster.read_band("B8", offset, window).unwrap();
+        let valid = band.iter().filter(|&&value| Some(value) != no_data_value).count();
+        assert_eq!(histogram.iter().sum::<u64>(), valid as u64);
+        // The extreme values land in the outer bins.
+        assert!(histogram[0] > 0 && histogram[15] > 0);
+
+        let stats = test_raster.band_stats("B8", offset, window).unwrap();
+        let range = (stats.min as u16, stats.min as u16 + 1);
+        let low = test_raster.band_histogram("B8", offset, window, 2, Some(range)).unwrap();
+        let count = |value| band.iter().filter(|&&v| v == value).count() as u64;
+        assert_eq!(low, [count(range.0), count(range.1)]);
+    }
+
+    #[rstest]
+    fn read_thumbnail(test_raster: Raster<Sentinel2>) {
+        let thumbnail = test_raster.read_thumbnail(vec!["B4", "B11", "B1"], 256).unwrap();
+        let (bands, width, height) = thumbnail.dim();
+        assert_eq!(bands, 3);
+        assert!(width.max(height) <= 256);
+        // The tile is square.
+        assert_eq!(width, height);
+    }
+
+    #[rstest]
+    fn read_bands_in_pool(test_raster: Raster<Sentinel2>) {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let (offset, window) = ((640, 480), (32, 24));
+        assert_eq!(
+            test_raster
+                .read_bands_in_pool(vec!["B4", "B8A", "B9"], offset, window, &pool)
+                .unwrap(),
+            test_raster.read_bands(vec!["B4", "B8A", "B9"], offset, window).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn read_bands_in_single_thread_pool(test_raster: Raster<Sentinel2>) {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let band_names = vec!["B2", "B4", "B5", "B11", "B9", "SCL"];
+        let (offset, window) = ((1200, 600), (48, 36));
+        let pooled = test_raster
+            .read_bands_in_pool(band_names.clone(), offset, window, &pool)
+            .unwrap();
+        assert_eq!(pooled.dim(), (band_names.len(), window.0, window.1));
+        assert_eq!(pooled, test_raster.read_bands(band_names, offset, window).unwrap());
+    }
+
+    #[rstest]
+    fn band_content_hash(test_raster: Raster<Sentinel2>) {
+        // Reference values of the FNV-1a specification.
+        assert_eq!(fnv1a([]), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(*b"a"), 0xaf63dc4c8601ec8c);
+        let window = (16, 16);
+        let hash = test_raster.band_content_hash("B4", (800, 800), window).unwrap();
+        let reopened = Sentinel2::raster_from(TEST_DATA).unwrap();
+        assert_eq!(reopened.band_content_hash("B4", (800, 800), window).unwrap(), hash);
+        assert_ne!(test_raster.band_content_hash("B4", (816, 800), window).unwrap(), hash);
+    }
+
+    #[rstest]
+    fn read_bands_standardized(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((2000, 2000), (32, 32));
+        let stats = HashMap::from([("B4".to_string(), (0.1, 0.05))]);
+        let standardized = test_raster
+            .read_bands_standardized(vec!["B4", "B3"], offset, window, &stats)
+            .unwrap();
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B4", "B3"], offset, window)
+            .unwrap();
+        let expected_b4 = reflectance.index_axis(Axis(0), 0).mapv(|value| (value - 0.1) / 0.05);
+        assert_eq!(standardized.index_axis(Axis(0), 0), expected_b4);
+        assert_eq!(standardized.index_axis(Axis(0), 1), reflectance.index_axis(Axis(0), 1));
+        for std in [0., -0.05, f32::NAN] {
+            let stats = HashMap::from([("B4".to_string(), (0.1, std))]);
+            assert!(matches!(
+                test_raster.read_bands_standardized(vec!["B4", "B3"], offset, window, &stats),
+                Err(Sentinel2ArrayError::InvalidBandStats { band_name, .. }) if band_name == "B4"
+            ));
+        }
+    }
+
+    #[test]
+    fn probe() {
+        let probe = Sentinel2::probe(TEST_DATA).unwrap();
+        assert_eq!(probe.metadata.tile_id().unwrap(), "T33PTM");
+        assert!(probe.metadata.product_start_time().unwrap().starts_with("2024-11-26"));
+        assert!(probe.band_names().contains(&"B4"));
+    }
+
+    #[rstest]
+    fn write_geotiff(test_raster: Raster<Sentinel2>) {
+        let out_path = std::env::temp_dir().join("rusterio_write_geotiff.tif");
+        let (offset, window) = ((300, 200), (40, 30));
+        let array = test_raster.read_bands(vec!["B8", "B4"], offset, window).unwrap();
+        test_raster.write_geotiff(&array, offset, &out_path).unwrap();
+
+        let written = gdal::Dataset::open(&out_path).unwrap();
+        assert_eq!(written.raster_count(), 2);
+        let [a, b, c, d, e, f] = test_raster.profile().unwrap().transform;
+        assert_eq!(
+            written.geo_transform().unwrap(),
+            [c + a * 300. + b * 200., a, b, f + d * 300. + e * 200., d, e]
+        );
+        let buffer = written.rasterband(2).unwrap().read_band_as::<u16>().unwrap();
+        let ((width, _), data) = buffer.into_shape_and_vec();
+        assert_eq!(width, 40);
+        assert!(array
+            .index_axis(Axis(0), 1)
+            .indexed_iter()
+            .all(|((x, y), &value)| data[y * width + x] == value));
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn summary_serde(test_raster: Raster<Sentinel2>) {
+        let summary = test_raster.summary().unwrap();
+        assert_eq!(summary.bands.len(), test_raster.band_names().len());
+        assert!(summary.bands.contains(&("B4".to_string(), 10.)));
+        assert_eq!(summary.transform, test_raster.profile().unwrap().transform);
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(serde_json::from_str::<RasterSummary>(&json).unwrap(), summary);
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn scene_index(test_raster: Raster<Sentinel2>) {
+        let index_path = std::env::temp_dir().join("rusterio_scene_index.json");
+        test_raster.save_index(&index_path).unwrap();
+        let indexed = Raster::<Sentinel2>::from_index(&index_path, TEST_DATA).unwrap();
+        assert_eq!(indexed.band_names(), test_raster.band_names());
+        assert_eq!(indexed.metadata.tile_id().unwrap(), test_raster.metadata.tile_id().unwrap());
+        let band_names = ["B4", "B11", "B1", "SCL"];
+        let (offset, window) = ((640, 480), (64, 48));
+        assert_eq!(
+            indexed.read_bands(band_names, offset, window).unwrap(),
+            test_raster.read_bands(band_names, offset, window).unwrap()
+        );
+        assert_eq!(
+            indexed.band_calibration("B4").unwrap(),
+            test_raster.band_calibration("B4").unwrap()
+        );
+        assert!(matches!(
+            Raster::<Sentinel2>::from_index(&index_path, "data/missing.SAFE.zip"),
+            Err(Sentinel2ArrayError::IndexDataNotFound(_))
+        ));
+
+        let mut index: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&index_path).unwrap()).unwrap();
+        let group = index["bands"][0]["group"].clone();
+        index["bands"][0]["group"] = 999.into();
+        std::fs::write(&index_path, index.to_string()).unwrap();
+        assert!(matches!(
+            Raster::<Sentinel2>::from_index(&index_path, TEST_DATA),
+            Err(Sentinel2ArrayError::InvalidIndex { group: 999, .. })
+        ));
+        index["bands"][0]["group"] = group;
+        for band_index in [0, 999] {
+            index["bands"][0]["index"] = band_index.into();
+            std::fs::write(&index_path, index.to_string()).unwrap();
+            assert!(matches!(
+                Raster::<Sentinel2>::from_index(&index_path, TEST_DATA),
+                Err(Sentinel2ArrayError::InvalidIndexBand { index, .. }) if index == band_index
+            ));
+        }
+        std::fs::remove_file(index_path).unwrap();
+    }
+
+    #[rstest]
+    fn write_cog(test_raster: Raster<Sentinel2>) {
+        let out_path = std::env::temp_dir().join("rusterio_write_cog.tif");
+        test_raster
+            .write_cog(vec!["B4", "B3", "B2"], (0, 0), (1024, 1024), &out_path)
+            .unwrap();
+        let cog = gdal::Dataset::open(&out_path).unwrap();
+        assert_eq!(
+            cog.metadata_item("LAYOUT", "IMAGE_STRUCTURE").as_deref(),
+            Some("COG")
+        );
+        assert_eq!(cog.raster_count(), 3);
+        let band = cog.rasterband(1).unwrap();
+        assert_eq!(band.block_size(), (512, 512));
+        assert!(band.overview_count().unwrap() > 0);
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[rstest]
+    fn write_array_cog(test_raster: Raster<Sentinel2>) {
+        let out_path = std::env::temp_dir().join("rusterio_write_array_cog.tif");
+        let (offset, window) = ((300, 200), (1024, 768));
+        let array = test_raster.read_bands(vec!["B8", "B4"], offset, window).unwrap();
+        test_raster.write_array_cog(&array, offset, &out_path, true).unwrap();
+
+        let cog = gdal::Dataset::open(&out_path).unwrap();
+        let [a, b, c, d, e, f] = test_raster.profile().unwrap().transform;
+        assert_eq!(
+            cog.geo_transform().unwrap(),
+            [c + a * 300. + b * 200., a, b, f + d * 300. + e * 200., d, e]
+        );
+        let band = cog.rasterband(2).unwrap();
+        let (block_width, block_height) = band.block_size();
+        assert!(block_height > 1 && block_width < window.0);
+        assert!(band.overview_count().unwrap() > 0);
+        let buffer = band.read_band_as::<u16>().unwrap();
+        let ((width, _), data) = buffer.into_shape_and_vec();
+        assert!(array
+            .index_axis(Axis(0), 1)
+            .indexed_iter()
+            .all(|((x, y), &value)| data[y * width + x] == value));
+
+        test_raster.write_array_cog(&array, offset, &out_path, false).unwrap();
+        let cog = gdal::Dataset::open(&out_path).unwrap();
+        assert_eq!(cog.rasterband(1).unwrap().overview_count().unwrap(), 0);
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[cfg(feature = "zarr")]
+    #[rstest]
+    fn write_zarr(test_raster: Raster<Sentinel2>) {
+        let out_path = std::env::temp_dir().join("rusterio_write_zarr.zarr");
+        let (offset, window) = ((300, 200), (600, 300));
+        let band_names = vec!["B4", "B11", "B1"];
+        test_raster.write_zarr(band_names.clone(), offset, window, &out_path).unwrap();
+
+        let read_json = |path: &Path| -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        let metadata = read_json(&out_path.join("bands/.zarray"));
+        assert_eq!(metadata["shape"], serde_json::json!([3, 300, 600]));
+        assert_eq!(metadata["chunks"], serde_json::json!([1, 300, 512]));
+        assert_eq!(metadata["dtype"], "<u2");
+        let attributes = read_json(&out_path.join(".zattrs"));
+        assert_eq!(attributes["band_names"], serde_json::json!(band_names));
+        assert_eq!(attributes["crs_wkt"], test_raster.profile().unwrap().crs.as_str());
+
+        // Second band, second chunk along x, padded past the window.
+        let chunk = std::fs::read(out_path.join("bands/1.0.1")).unwrap();
+        assert_eq!(chunk.len(), 300 * 512 * 2);
+        let array = test_raster.read_bands(band_names, offset, window).unwrap();
+        let at = |x: usize, y: usize| {
+            let index = (y * 512 + x - 512) * 2;
+            u16::from_le_bytes([chunk[index], chunk[index + 1]])
+        };
+        assert_eq!(at(520, 10), array[[1, 520, 10]]);
+        assert_eq!(at(599, 299), array[[1, 599, 299]]);
+        assert_eq!(at(600, 0), 0);
+        std::fs::remove_dir_all(&out_path).unwrap();
+
+        for window in [(0, 300), (600, 0)] {
+            assert!(matches!(
+                test_raster.write_zarr(["B4"], offset, window, &out_path),
+                Err(Sentinel2ArrayError::EmptyWindow(empty)) if empty == window
+            ));
+        }
+        assert!(!out_path.exists());
+    }
+
+    #[cfg(feature = "netcdf")]
+    #[rstest]
+    fn write_netcdf(test_raster: Raster<Sentinel2>) {
+        let out_path = std::env::temp_dir().join("rusterio_write_netcdf.nc");
+        let (offset, window) = ((300, 200), (60, 30));
+        let band_names = vec!["B4", "B11"];
+        test_raster.write_netcdf(band_names.clone(), offset, window, &out_path).unwrap();
+
+        let options = gdal::DatasetOptions {
+            open_flags: gdal::GdalOpenFlags::GDAL_OF_MULTIDIM_RASTER,
+            ..Default::default()
+        };
+        let dataset = gdal::Dataset::open_ex(&out_path, options).unwrap();
+        let root = dataset.root_group().unwrap();
+        let open_array = |name: &str| root.open_md_array(name, Default::default()).unwrap();
+        let b11 = open_array("B11");
+        let dimensions = b11.dimensions().unwrap();
+        let dimensions = dimensions.iter().map(|dimension| (dimension.name(), dimension.size()));
+        assert_eq!(
+            dimensions.collect::<Vec<_>>(),
+            vec![("y".to_string(), 30), ("x".to_string(), 60)]
+        );
+        let xs = open_array("x").read_as::<f64>(vec![0], vec![60]).unwrap();
+        let ys = open_array("y").read_as::<f64>(vec![0], vec![30]).unwrap();
+        assert!(xs.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ys.windows(2).all(|pair| pair[0] > pair[1]));
+        let wkt = open_array("crs").attribute("crs_wkt").unwrap().read_as_string();
+        let crs = gdal::spatial_ref::SpatialRef::from_wkt(&wkt).unwrap();
+        assert_eq!(crs, test_raster.crs().unwrap());
+
+        let array = test_raster.read_bands(band_names, offset, window).unwrap();
+        let values = b11.read_as::<u16>(vec![0, 0], vec![30, 60]).unwrap();
+        assert_eq!(values[10 * 60 + 20], array[[1, 20, 10]]);
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[rstest]
+    fn estimate_read_bytes(test_raster: Raster<Sentinel2>) {
+        let (band_names, window) = (vec!["B4", "B11", "B1"], (100, 50));
+        let array = test_raster.read_bands(band_names.clone(), (0, 0), window).unwrap();
+        assert_eq!(
+            test_raster.estimate_read_bytes(&band_names, window),
+            array.len() * std::mem::size_of::<u16>()
+        );
+        assert!(matches!(
+            test_raster
+                .read()
+                .bands(band_names)
+                .window((0, 0), window)
+                .max_read_bytes(1000)
+                .execute(),
+            Err(Sentinel2ArrayError::ReadTooLarge { requested: 30000, limit: 1000 })
+        ));
+    }
+
+    #[rstest]
+    fn read_view_angles(test_raster: Raster<Sentinel2>) {
+        let (zenith, azimuth) = test_raster
+            .read_view_angles("B8A", (2500, 2500), (64, 64))
+            .unwrap();
+        assert_eq!(zenith.dim(), (64, 64));
+        assert!(zenith.iter().all(|&angle| (0. ..90.).contains(&angle)));
+        assert!(azimuth.iter().all(|&angle| (0. ..360.).contains(&angle)));
+        assert!(matches!(
+            test_raster.read_view_angles("B99", (0, 0), (1, 1)),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn raster_from_band_map(test_raster: Raster<Sentinel2>) {
+        // The 10m subdataset holds B4, B3, B2 and B8, swap B4 and B8.
+        let subdataset_name = Sentinel2::probe(TEST_DATA).unwrap().subdatasets[0].name.clone();
+        let band_map = HashMap::from([
+            ("B4".to_string(), (subdataset_name.clone(), 4)),
+            ("B8".to_string(), (subdataset_name, 1)),
+        ]);
+        let remapped = Sentinel2::raster_from_band_map(TEST_DATA, band_map).unwrap();
+        let (offset, window) = ((1000, 1000), (32, 32));
+        assert_eq!(
+            remapped.read_bands(vec!["B4", "B8"], offset, window).unwrap(),
+            test_raster.read_bands(vec!["B8", "B4"], offset, window).unwrap()
+        );
+        assert!(matches!(
+            remapped.read_bands(vec!["B3"], offset, window),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_labeled(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((1000, 1000), (64, 48));
+        let (reflectance, labels) = test_raster
+            .read_bands_labeled(vec!["B4", "B8"], offset, window)
+            .unwrap();
+        assert_eq!(reflectance.dim(), (2, 64, 48));
+        assert_eq!(labels.dim(), window);
+        assert!(labels.iter().all(|&class| class <= 11));
+    }
+
+    #[rstest]
+    fn read_bands_convert(test_raster: Raster<Sentinel2>) {
+        struct Doubled;
+        impl PixelConverter for Doubled {
+            fn convert(&self, _band_name: &str, value: u16) -> f32 {
+                value as f32 * 2.
+            }
+        }
+        let (offset, window) = ((500, 500), (32, 32));
+        let doubled = test_raster
+            .read_bands_convert(vec!["B4", "B11"], offset, window, &Doubled)
+            .unwrap();
+        let identity = test_raster
+            .read_bands_convert(vec!["B4", "B11"], offset, window, &Identity)
+            .unwrap();
+        let direct = test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap();
+        assert_eq!(identity, direct.mapv(|value| value as f32));
+        assert_eq!(doubled, identity * 2.);
+    }
+
+    #[rstest]
+    fn read_full(test_raster: Raster<Sentinel2>) {
+        let size = test_raster.size().unwrap();
+        assert_eq!(size, (10980, 10980));
+        let full = test_raster.read_full(vec!["B11"]).unwrap();
+        assert_eq!(full.dim(), (1, size.0, size.1));
+        let (offset, window) = ((3000, 4700), (64, 64));
+        assert_eq!(
+            full.slice(ndarray::s![.., 3000..3064, 4700..4764]),
+            test_raster.read_bands(vec!["B11"], offset, window).unwrap()
+        );
+        assert!(matches!(
+            test_raster
+                .read()
+                .bands(vec!["B4"])
+                .full_extent()
+                .max_read_bytes(1 << 20)
+                .execute(),
+            Err(Sentinel2ArrayError::ReadTooLarge { .. })
+        ));
+    }
+
+    #[rstest]
+    fn transform_report(test_raster: Raster<Sentinel2>) {
+        let report = test_raster.transform_report();
+        assert_eq!(
+            report.iter().map(|&(resolution, _)| resolution).collect::<Vec<_>>(),
+            vec![10, 20, 60]
+        );
+        assert!(report[0].1 >= 4);
+        assert_eq!(
+            report.iter().map(|&(_, count)| count).sum::<usize>(),
+            test_raster.band_names().len()
+        );
+    }
+
+    #[rstest]
+    fn bit_depth(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.bit_depth().unwrap(), 12);
+        assert_eq!(test_raster.max_digital_number().unwrap(), 4095);
+    }
+
+    #[rstest]
+    fn read_bands_rowcol(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((100, 7), (5, 3));
+        let rowcol = test_raster.read_bands_rowcol(vec!["B4"], offset, window).unwrap();
+        assert_eq!(rowcol.dim(), (1, 3, 5));
+        // Plain GDAL reads, like rasterio's, are row major.
+        let subdataset_name = &Sentinel2::probe(TEST_DATA).unwrap().subdatasets[0].name;
+        let buffer = gdal::Dataset::open(subdataset_name)
+            .unwrap()
+            .rasterband(1)
+            .unwrap()
+            .read_as::<u16>(offset, window, window, None)
+            .unwrap();
+        let (shape, data) = buffer.into_shape_and_vec();
+        assert_eq!(
+            rowcol.index_axis(Axis(0), 0),
+            Array2::from_shape_vec((shape.1, shape.0), data).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn band_cached_statistics(test_raster: Raster<Sentinel2>) {
+        assert!(test_raster.band_cached_statistics("B4").is_ok());
+        let mut metadata = Metadata::new("B4".into());
+        assert_eq!(metadata.cached_statistics().unwrap(), None);
+        for (key, value) in [
+            ("STATISTICS_MINIMUM", "1"),
+            ("STATISTICS_MAXIMUM", "4095"),
+            ("STATISTICS_MEAN", "812.5"),
+            ("STATISTICS_STDDEV", "301.25"),
+        ] {
+            metadata.insert(key.into(), value.into());
+        }
+        assert_eq!(
+            metadata.cached_statistics().unwrap(),
+            Some(BandStats { min: 1., max: 4095., mean: 812.5, std_dev: 301.25 })
+        );
+    }
+
+    #[rstest]
+    fn read_band_windows(test_raster: Raster<Sentinel2>) {
+        let windows = (0..10)
+            .map(|i| ((i * 517, 10979 - i * 731 - 16), (16 + i as usize, 16)))
+            .collect::<Vec<_>>();
+        let batched = test_raster.read_band_windows("B4", &windows).unwrap();
+        assert_eq!(batched.len(), windows.len());
+        for (chip, &(offset, window)) in batched.iter().zip(&windows) {
+            let single = test_raster.read_bands(vec!["B4"], offset, window).unwrap();
+            assert_eq!(chip, single.index_axis(Axis(0), 0));
+        }
+    }
+
+    #[rstest]
+    fn profile(test_raster: Raster<Sentinel2>) {
+        let profile = test_raster.profile().unwrap();
+        assert_eq!((profile.width, profile.height), test_raster.size().unwrap());
+        assert_eq!(profile.count, test_raster.band_names().len());
+        assert_eq!(profile.dtype, "uint16");
+        assert_eq!(profile.transform[0], 10.);
+        assert_eq!(profile.transform[4], -10.);
+        assert_eq!(profile.to_map()["width"], "10980");
+    }
+
+    #[test]
+    fn sample_bilinear_no_data_boundary() {
+        // x = 0 is no data, x = 1 is valid.
+        let band_raster = Array2::from_shape_vec((2, 2), vec![0, 0, 100, 300]).unwrap();
+        let between = |x, y| sample_bilinear(&band_raster, Point2::new(x, y), Some(0));
+        assert_eq!(between(1., 1.), 200);
+        assert_eq!(between(0.6, 0.5), 100);
+        let no_data = Array2::<u16>::zeros((2, 2));
+        assert_eq!(sample_bilinear(&no_data, Point2::new(1., 1.), Some(0)), 0);
+    }
+
+    #[rstest]
+    fn read_bands_bilinear(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((2000, 2000), (64, 64));
+        let bilinear = test_raster
+            .read()
+            .bands(vec!["B4", "B11"])
+            .window(offset, window)
+            .resampling(Resampling::Bilinear)
+            .execute()
+            .unwrap();
+        let nearest = test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap();
+        assert_eq!(bilinear.dim(), nearest.dim());
+        // Same grid bands are left as read.
+        assert_eq!(bilinear.index_axis(Axis(0), 0), nearest.index_axis(Axis(0), 0));
+        // Hanging past the right edge of the scene, with no fill set.
+        assert!(matches!(
+            test_raster
+                .read()
+                .bands(vec!["B4", "B11"])
+                .window((10970, 0), window)
+                .resampling(Resampling::Bilinear)
+                .execute(),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+        assert!(matches!(
+            test_raster.read_bands_resampled(vec!["B4"], (-1, 0), window, Resampling::Bilinear),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_resampled_60m(test_raster: Raster<Sentinel2>) {
+        // Aligned with the 60 m grid, every B1 pixel covers 6x6 read pixels.
+        let (offset, window) = ((1800, 1800), (36, 36));
+        let read = |resampling| {
+            test_raster
+                .read_bands_resampled(vec!["B4", "B1"], offset, window, resampling)
+                .unwrap()
+                .index_axis_move(Axis(0), 1)
+        };
+        let (nearest, bilinear) = (read(Resampling::Nearest), read(Resampling::Bilinear));
+        let block = nearest.slice(ndarray::s![..6, ..6]);
+        assert!(block.iter().all(|&value| value == nearest[[0, 0]]));
+        assert_ne!(nearest, bilinear);
+        let mean = |band: &Array2<u16>| band.mapv(f64::from).mean().unwrap();
+        assert!((mean(&bilinear) / mean(&nearest) - 1.).abs() < 0.05);
+    }
+
+    #[rstest]
+    fn thumbnail(test_raster: Raster<Sentinel2>) {
+        let thumbnail = test_raster.thumbnail(256).unwrap();
+        let (channels, width, height) = thumbnail.dim();
+        assert_eq!(channels, 3);
+        assert!(width <= 256 && height <= 256);
+        assert_eq!(width.max(height), 256);
+    }
+
+    #[cfg(feature = "arrow")]
+    #[rstest]
+    fn read_bands_arrow(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((700, 900), (20, 10));
+        let batch = test_raster
+            .read_bands_arrow(vec!["B4", "B11"], offset, window)
+            .unwrap();
+        assert_eq!(batch.num_rows(), 200);
+        let schema = batch.schema();
+        let columns = schema.fields().iter().map(|field| field.name().as_str()).collect::<Vec<_>>();
+        assert_eq!(columns, vec!["x", "y", "B4", "B11"]);
+    }
+
+    #[rstest]
+    fn band_geo_transform(test_raster: Raster<Sentinel2>) {
+        let geo_transform = test_raster.band_geo_transform("B11").unwrap();
+        assert_eq!((geo_transform[1], geo_transform[5]), (20., -20.));
+        assert_eq!(
+            geo_transform[0],
+            test_raster.band_geo_transform("B4").unwrap()[0]
+        );
+    }
+
+    #[rstest]
+    fn scene_geometry(test_raster: Raster<Sentinel2>) {
+        let geometry = test_raster.metadata.scene_geometry().unwrap();
+        assert!((0. ..90.).contains(&geometry.sun_zenith));
+        assert!((0. ..360.).contains(&geometry.sun_azimuth));
+        assert!((0. ..15.).contains(&geometry.view_zenith));
+        assert!((0. ..360.).contains(&geometry.view_azimuth));
+    }
+
+    #[rstest]
+    fn atmospheric_parameters(test_raster: Raster<Sentinel2>) {
+        let parameters = test_raster.metadata.atmospheric_parameters().unwrap();
+        assert!((200. ..500.).contains(&parameters.ozone));
+        assert!(!parameters.ozone_source.is_empty());
+        assert!((0. ..10.).contains(&parameters.water_vapour.unwrap()));
+        assert!((0. ..5.).contains(&parameters.aerosol_optical_thickness.unwrap()));
+    }
+
+    #[rstest]
+    fn read_bands_like(test_raster: Raster<Sentinel2>) {
+        // A grid of the fixture's 10m bands, shifted by (35, 52) pixels.
+        let (offset, window) = ((35, 52), (40, 30));
+        let mut geo_transform = test_raster.band_geo_transform("B4").unwrap();
+        geo_transform[0] += offset.0 as f64 * geo_transform[1];
+        geo_transform[3] += offset.1 as f64 * geo_transform[5];
+        let (mut dataset, path) = gtiff_dataset("read_bands_like", geo_transform, window, 1);
+        dataset.set_projection(&test_raster.profile().unwrap().crs).unwrap();
+        let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+        let reference = Raster::<Reference>::new(
+            Bands::from_iter([("REF".to_string(), BandInfo::new(band_group, 1, ()))]),
+            (),
+        );
+
+        let like = test_raster.read_bands_like(vec!["B4"], &reference).unwrap();
+        assert_eq!(like.dim(), (1, window.0, window.1));
+        let direct = test_raster.read_bands(vec!["B4"], offset, window).unwrap();
+        assert!(like
+            .iter()
+            .zip(direct.iter())
+            .all(|(&like, &direct)| (like - direct as f32).abs() < 0.5));
+        let limited = test_raster
+            .read_bands_like_with_mem_limit(vec!["B4"], &reference, 1)
+            .unwrap();
+        assert_eq!(limited, like);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn masks(test_raster: Raster<Sentinel2>) {
+        let masks = test_raster.masks();
+        let cloud_probability = masks
+            .iter()
+            .find(|mask| mask.name == "MSK_CLDPRB_20m")
+            .unwrap();
+        assert_eq!(cloud_probability.resolution, 20);
+        let mask = test_raster.read_mask("MSK_CLDPRB_20m", (0, 0), (16, 16)).unwrap();
+        assert_eq!(mask.dim(), (16, 16));
+        assert!(mask.iter().all(|&probability| probability <= 100));
+        assert!(matches!(
+            test_raster.read_mask("MSK_NOPE", (0, 0), (1, 1)),
+            Err(Sentinel2ArrayError::MaskNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn dilate_single_pixel() {
+        let mut mask = Array2::from_elem((5, 4), false);
+        mask[[1, 2]] = true;
+        let dilated = dilate(&mask, 1);
+        for ((x, y), &masked) in dilated.indexed_iter() {
+            assert_eq!(masked, (0..=2).contains(&x) && (1..=3).contains(&y));
+        }
+        // Window edges just clip the buffer.
+        let mut corner = Array2::from_elem((3, 3), false);
+        corner[[0, 0]] = true;
+        assert_eq!(dilate(&corner, 1).iter().filter(|&&masked| masked).count(), 4);
+    }
+
+    #[rstest]
+    fn cloud_mask(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((100, 100), (64, 64));
+        let mask = test_raster.cloud_mask(offset, window, 0).unwrap();
+        let buffered = test_raster.cloud_mask(offset, window, 2).unwrap();
+        assert_eq!(mask.dim(), window);
+        assert!(mask.iter().zip(buffered.iter()).all(|(&mask, &buffered)| !mask || buffered));
+        let scl = test_raster.read_scl(offset, window).unwrap();
+        assert_eq!(mask, scl.mapv(|class| matches!(class, 3 | 8 | 9 | 10)));
+    }
+
+    #[rstest]
+    fn read_bands_f64(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((0, 0), (1024, 1024));
+        let digital_numbers = test_raster.read_bands(vec!["B4"], offset, window).unwrap();
+        let quantification_value = test_raster.metadata.boa_quantification_value().unwrap();
+        let add_offset = test_raster.metadata.boa_add_offset().unwrap();
+        let exact = (digital_numbers.iter().map(|&value| value as u64).sum::<u64>() as f64
+            + digital_numbers.len() as f64 * add_offset as f64)
+            / quantification_value as f64;
+        // Sequential sums, the rounding errors of f32 pile up over 2^20 terms.
+        let sum_f32 = test_raster
+            .read_bands_reflectance(vec!["B4"], offset, window)
+            .unwrap()
+            .iter()
+            .fold(0f32, |sum, &value| sum + value);
+        let sum_f64 = test_raster
+            .read_bands_f64(vec!["B4"], offset, window)
+            .unwrap()
+            .iter()
+            .fold(0f64, |sum, &value| sum + value);
+        assert!((sum_f64 - exact).abs() < (sum_f32 as f64 - exact).abs());
+        assert!((sum_f64 - exact).abs() / exact < 1e-9);
+    }
+
+    #[test]
+    fn raster_from_strict() {
+        assert!(Sentinel2::raster_from_strict(TEST_DATA).is_ok());
+        let subdataset_name = Sentinel2::probe(TEST_DATA).unwrap().subdatasets[0].name.clone();
+        let incomplete = Sentinel2::raster_from_band_map(
+            TEST_DATA,
+            HashMap::from([("B4".to_string(), (subdataset_name, 1))]),
+        )
+        .unwrap();
+        match incomplete.validate() {
+            Err(Sentinel2ArrayError::ValidationFailed(issues)) => {
+                assert!(issues.contains(&"missing L2A band B2".to_string()));
+                assert!(!issues.iter().any(|issue| issue.contains("B4")));
+            }
+            other => panic!("expected ValidationFailed, got {other:?}"),
+        }
+
+        // A band of 10.7 m pixels, which truncate to 10.
+        let geo_transform = [300000., 10.7, 0., 1600000., 0., -10.7];
+        let (dataset, path) = gtiff_dataset("raster_from_strict", geo_transform, (4, 4), 1);
+        let mut raster_band = dataset.rasterband(1).unwrap();
+        raster_band.set_metadata_item("BANDNAME", "B4", "").unwrap();
+        let (band_name, band_metadata) = Sentinel2::parse_rasterband_metadata(raster_band).unwrap();
+        let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+        let mut metadata = Metadata::new("non standard product".into());
+        metadata.insert("PRODUCT_TYPE".into(), "S2MSI2A".into());
+        let non_standard = Raster::<Sentinel2>::new(
+            Bands::from_iter([(band_name, BandInfo::new(band_group, 1, band_metadata))]),
+            metadata.into(),
+        );
+        match non_standard.validate() {
+            Err(Sentinel2ArrayError::ValidationFailed(issues)) => {
+                assert!(issues.contains(&"band B4 has non standard resolution 10.7".to_string()));
+            }
+            other => panic!("expected ValidationFailed, got {other:?}"),
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn read_band_source_window(test_raster: Raster<Sentinel2>) {
+        let (src_offset, src_window) = ((1234, 567), (48, 32));
+        let source_window = test_raster
+            .read_band_source_window("B4", src_offset, src_window)
+            .unwrap();
+        let subdataset_name = &Sentinel2::probe(TEST_DATA).unwrap().subdatasets[0].name;
+        let dataset = gdal::Dataset::open(subdataset_name).unwrap();
+        let direct = rasters::reader::DatasetReader(dataset, 1)
+            .read_as_array::<u16>(src_offset, src_window)
+            .unwrap();
+        assert_eq!(source_window, direct);
+    }
+
+    #[rstest]
+    fn band_source(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((1234, 567), (48, 32));
+        let (path, index) = test_raster.band_source("B4").unwrap();
+        let direct = rasters::reader::DatasetReader(gdal::Dataset::open(path).unwrap(), index)
+            .read_as_array::<u16>(offset, window)
+            .unwrap();
+        assert_eq!(direct, test_raster.read_band("B4", offset, window).unwrap());
+        assert!(matches!(
+            test_raster.band_source("B13"),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn read_native_band_as_read(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((1234, 567), (48, 32));
+        let (path, index) = test_raster.band_source("B4").unwrap();
+        let direct = rasters::reader::DatasetReader(gdal::Dataset::open(path).unwrap(), index)
+            .read_as_array::<u16>(offset, window)
+            .unwrap();
+        assert_eq!(test_raster.read_band("B4", offset, window).unwrap(), direct);
+        for (resampling, anchor) in [
+            (Resampling::Bilinear, PixelAnchor::Centre),
+            (Resampling::Nearest, PixelAnchor::Corner),
+            (Resampling::Bilinear, PixelAnchor::Corner),
+        ] {
+            let array = test_raster
+                .read_bands_anchored(&["B4", "B11"], offset, window, resampling, anchor)
+                .unwrap();
+            assert_eq!(array.index_axis(Axis(0), 0), direct, "{resampling:?} at {anchor:?}");
+        }
+    }
+
+    #[rstest]
+    fn metadata_diff(test_raster: Raster<Sentinel2>) {
+        let reopened = Sentinel2::raster_from(TEST_DATA).unwrap();
+        assert!(test_raster.metadata_diff(&reopened).is_empty());
+
+        let mut metadata = Metadata::new("product".into());
+        metadata.insert("PROCESSING_BASELINE".into(), "05.11".into());
+        metadata.insert("PRODUCT_TYPE".into(), "S2MSI2A".into());
+        let mut reprocessed = Metadata::new("reprocessed".into());
+        reprocessed.insert("PROCESSING_BASELINE".into(), "05.10".into());
+        reprocessed.insert("PRODUCT_TYPE".into(), "S2MSI2A".into());
+        reprocessed.insert("BOA_ADD_OFFSET".into(), "-1000".into());
+        assert_eq!(
+            metadata.diff(&reprocessed),
+            vec![
+                MetadataDelta {
+                    key: "BOA_ADD_OFFSET".into(),
+                    self_value: None,
+                    other_value: Some("-1000".into()),
+                },
+                MetadataDelta {
+                    key: "PROCESSING_BASELINE".into(),
+                    self_value: Some("05.11".into()),
+                    other_value: Some("05.10".into()),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn reflectance_calibration(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.band_calibration("B4").unwrap(), None);
+        let reflectance = Reflectance::new(&test_raster.metadata)
+            .unwrap()
+            .with_calibration("B4", 2e-4, -0.1);
+        assert!((reflectance.convert("B4", 2000) - 0.3).abs() < 1e-6);
+        let quantification_value = test_raster.metadata.boa_quantification_value().unwrap();
+        let add_offset = test_raster.metadata.boa_add_offset().unwrap();
+        assert_eq!(
+            reflectance.convert("B3", 2000),
+            ((2000. + add_offset as f64) / quantification_value as f64) as f32
+        );
+    }
+
+    #[test]
+    fn boa_offsets() {
+        let mut metadata = Metadata::new("product".into());
+        for (key, value) in [
+            ("BOA_QUANTIFICATION_VALUE", "10000"),
+            ("BOA_ADD_OFFSET", "-200"),
+            ("BOA_ADD_OFFSET_B2", "-1000"),
+            // `band_id` 8 is B8A.
+            ("BOA_ADD_OFFSET_8", "-500"),
+        ] {
+            metadata.insert(key.into(), value.into());
+        }
+        let metadata = sentinel2::RasterMetadata::from(metadata);
+        assert_eq!(
+            metadata.boa_offsets().unwrap(),
+            HashMap::from([("B2".to_string(), -1000.), ("B8A".to_string(), -500.)])
+        );
+        let reflectance = Reflectance::new(&metadata).unwrap();
+        assert_eq!(reflectance.convert("B2", 2000), 0.1);
+        assert_eq!(reflectance.convert("B8A", 2000), 0.15);
+        assert_eq!(reflectance.convert("B4", 2000), 0.18);
+    }
+
+    #[rstest]
+    fn read_bands_combine(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((3000, 3000), (40, 30));
+        let ratio = test_raster
+            .read_bands_combine("B8", "B11", offset, window, |a, b| a / b)
+            .unwrap();
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B8", "B11"], offset, window)
+            .unwrap();
+        assert_eq!(ratio.dim(), window);
+        assert_eq!(
+            ratio,
+            &reflectance.index_axis(Axis(0), 0) / &reflectance.index_axis(Axis(0), 1)
+        );
+    }
+
+    #[rstest]
+    fn read_bands_reflectance_range(test_raster: Raster<Sentinel2>) {
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B2", "B4", "B8", "B11"], (5000, 5000), (64, 64))
+            .unwrap();
+        assert!(reflectance.iter().all(|value| (-0.1..=1.6).contains(value)));
+        let mean = reflectance.mean().unwrap();
+        assert!((0. ..1.).contains(&mean));
+    }
+
+    #[rstest]
+    fn read_grouped(test_raster: Raster<Sentinel2>) {
+        let grouped = test_raster
+            .read_grouped(vec!["B4", "B11", "B8", "B5"], (200, 100), (64, 48))
+            .unwrap();
+        assert_eq!(grouped[&10].dim(), (2, 64, 48));
+        assert_eq!(grouped[&20].dim(), (2, 32, 24));
+        assert_eq!(
+            grouped[&20].index_axis(Axis(0), 0),
+            test_raster
+                .read_band_source_window("B11", (100, 50), (32, 24))
+                .unwrap()
+        );
+    }
+
+    #[rstest]
+    fn read_bands_at(test_raster: Raster<Sentinel2>) {
+        let array = test_raster.read_bands_at(["B2", "B11"], (100, 50), (32, 24), 20).unwrap();
+        assert_eq!(array.dim(), (2, 32, 24));
+        assert_eq!(
+            array.index_axis(Axis(0), 1),
+            test_raster.read_band_source_window("B11", (100, 50), (32, 24)).unwrap()
+        );
+        // 20 m pixel centres fall on the lower right 10 m pixel of each 2 x 2.
+        let b2 = test_raster.read_band("B2", (200, 100), (64, 48)).unwrap();
+        assert_eq!(array.index_axis(Axis(0), 0), b2.slice(s![1..;2, 1..;2]));
+        assert!(matches!(
+            test_raster.read_bands_at(["B2"], (0, 0), (1, 1), 30),
+            Err(Sentinel2ArrayError::NoBandsAtResolution(30))
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_map(test_raster: Raster<Sentinel2>) {
+        let (band_names, offset, window) = (vec!["B3", "B11", "B1"], (400, 300), (16, 12));
+        let map = test_raster.read_bands_map(band_names.clone(), offset, window).unwrap();
+        let mut keys = map.keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec!["B1", "B11", "B3"]);
+        let stacked = test_raster.read_bands(band_names.clone(), offset, window).unwrap();
+        for (band_name, band) in band_names.iter().zip(stacked.outer_iter()) {
+            assert_eq!(map[*band_name], band);
+        }
+    }
+
+    #[rstest]
+    fn read_bands_native(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((200, 100), (64, 48));
+        let native = test_raster
+            .read_bands_native(vec!["B4", "B11", "B8", "B5"], offset, window)
+            .unwrap();
+        assert_eq!(native["B4"].dim(), (64, 48));
+        assert_eq!(native["B11"].dim(), (32, 24));
+        assert_eq!(native["B8"], test_raster.read_band("B8", offset, window).unwrap());
+        assert_eq!(
+            native["B5"],
+            test_raster.read_band_source_window("B5", (100, 50), (32, 24)).unwrap()
+        );
+    }
+
+    #[cfg(feature = "candle")]
+    #[rstest]
+    fn read_bands_tensor(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((300, 400), (40, 24));
+        let tensor = test_raster
+            .read_bands_tensor(vec!["B4", "B3", "B2"], offset, window)
+            .unwrap();
+        assert_eq!(tensor.dims(), &[3, 24, 40]);
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B4", "B3", "B2"], offset, window)
+            .unwrap();
+        let (band, x, y) = (1, 7, 3);
+        let value = tensor.get(band).unwrap().get(y).unwrap().get(x).unwrap();
+        assert_eq!(value.to_scalar::<f32>().unwrap(), reflectance[[band, x, y]]);
+    }
+
+    #[rstest]
+    fn clear_pixel_count(test_raster: Raster<Sentinel2>) {
+        let classes = vec![4, 5, 6, 8, 9, 10, 3, 0, 4, 4, 11, 7];
+        let scl = Array2::from_shape_vec((3, 4), classes).unwrap();
+        assert_eq!(count_clear(scl.view()), 5);
+        let (offset, window) = ((10, 10), (32, 32));
+        let clear = test_raster.clear_pixel_count(offset, window).unwrap();
+        let scl = test_raster.read_scl(offset, window).unwrap();
+        assert_eq!(clear, count_clear(scl.view()));
+    }
+
+    #[rstest]
+    fn read_to_mem_dataset(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((640, 320), (16, 8));
+        let dataset = test_raster
+            .read_to_mem_dataset(vec!["B4", "B11"], offset, window)
+            .unwrap();
+        assert_eq!(dataset.raster_size(), window);
+        let mut geo_transform = test_raster.band_geo_transform("B4").unwrap();
+        geo_transform[0] += offset.0 as f64 * geo_transform[1];
+        geo_transform[3] += offset.1 as f64 * geo_transform[5];
+        assert_eq!(dataset.geo_transform().unwrap(), geo_transform);
+        let band = dataset.rasterband(2).unwrap();
+        assert_eq!(band.metadata_item("BANDNAME", "").as_deref(), Some("B11"));
+        let pixel = band.read_as::<u16>((5, 3), (1, 1), (1, 1), None).unwrap();
+        let array = test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap();
+        assert_eq!(pixel.data()[0], array[[1, 5, 3]]);
+    }
+
+    #[test]
+    fn to_le_bytes_layout() {
+        // Two bands of 2x1 pixels, `[[c, x, y]]`.
+        let array =
+            ndarray::Array3::from_shape_vec((2, 2, 1), vec![0x0102, 0x0304, 0xA0B0, 0x00FF])
+                .unwrap();
+        assert_eq!(
+            to_le_bytes(&array),
+            vec![0x02, 0x01, 0x04, 0x03, 0xB0, 0xA0, 0xFF, 0x00]
+        );
+        let rows = ndarray::Array3::from_shape_vec((1, 2, 2), vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(to_le_bytes(&rows), vec![1, 0, 3, 0, 2, 0, 4, 0]);
+    }
+
+    #[rstest]
+    fn read_bands_le_bytes(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((0, 0), (3, 2));
+        let bytes = test_raster.read_bands_le_bytes(vec!["B4"], offset, window).unwrap();
+        let array = test_raster.read_bands(vec!["B4"], offset, window).unwrap();
+        assert_eq!(bytes.len(), 12);
+        assert_eq!(u16::from_le_bytes([bytes[2], bytes[3]]), array[[0, 1, 0]]);
+        assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]), array[[0, 0, 1]]);
+    }
+
+    #[rstest]
+    fn product_nodata(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.product_nodata().unwrap(), 0);
+        let no_data_value = test_raster
+            .band_no_data_value("B4")
+            .unwrap()
+            .map_or(0, |no_data_value| no_data_value as u16);
+        let (offset, window) = ((0, 0), (256, 256));
+        let masked = test_raster
+            .read_bands_reflectance_masked(vec!["B4"], offset, window)
+            .unwrap();
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B4"], offset, window)
+            .unwrap();
+        let digital_numbers = test_raster.read_bands(vec!["B4"], offset, window).unwrap();
+        for ((masked, reflectance), digital_number) in
+            masked.iter().zip(reflectance.iter()).zip(digital_numbers.iter())
+        {
+            if *digital_number == no_data_value {
+                assert!(masked.is_nan());
+            } else {
+                assert_eq!(masked, reflectance);
+            }
+        }
+    }
+
+    #[rstest]
+    fn pixel_anchor(test_raster: Raster<Sentinel2>) {
+        // A 20 m band sampled from the 10 m grid.
+        let transform = nalgebra::Matrix3::new(0.5, 0., 0., 0., 0.5, 0., 0., 0., 1.);
+        let corner = transform.transform_point(&PixelAnchor::Corner.point(3, 7));
+        let centre = transform.transform_point(&PixelAnchor::Centre.point(3, 7));
+        assert_eq!(centre - corner, nalgebra::Vector2::new(0.25, 0.25));
+
+        // Whole resolution ratios land in the same source pixels either way.
+        let read = |anchor| {
+            test_raster
+                .read()
+                .bands(vec!["B4", "B5"])
+                .window((10, 10), (64, 64))
+                .pixel_anchor(anchor)
+                .execute()
+                .unwrap()
+        };
+        assert_eq!(read(PixelAnchor::Centre), read(PixelAnchor::Corner));
+    }
+
+    #[test]
+    fn nearest_sampling_of_upsampled_band() {
+        // A 10 m band and a 20 m one whose pixels hold their column.
+        let band_group = |name: &str, resolution: f64, size: usize| {
+            let geo_transform = [5e5, resolution, 0., 5e6, 0., -resolution];
+            let (dataset, path) =
+                gtiff_dataset(&format!("gradient_{name}"), geo_transform, (size, size), 1);
+            let data = (0..size * size).map(|index| (index % size) as u16).collect();
+            let mut buffer = gdal::raster::Buffer::new((size, size), data);
+            dataset.rasterband(1).unwrap().write((0, 0), (size, size), &mut buffer).unwrap();
+            (Arc::new(BandGroup::new(&dataset).unwrap()), path)
+        };
+        let (fine, fine_path) = band_group("10m", 10., 20);
+        let (coarse, coarse_path) = band_group("20m", 20., 10);
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([
+                ("B4".to_string(), BandInfo::new(fine, 1, ())),
+                ("B5".to_string(), BandInfo::new(coarse, 1, ())),
+            ]),
+            (),
+        );
+
+        // The centre of 10 m pixel x lies in 20 m pixel x / 2, the one whose
+        // centre is nearest, with no shift towards either neighbour.
+        let array = raster.read_bands(["B4", "B5"], (1, 0), (19, 20)).unwrap();
+        for ((x, _), &column) in array.index_axis(Axis(0), 1).indexed_iter() {
+            let fine_column = x + 1;
+            assert_eq!(column as usize, fine_column / 2);
+        }
+        std::fs::remove_file(fine_path).unwrap();
+        std::fs::remove_file(coarse_path).unwrap();
+    }
+
+    #[test]
+    fn circle_mask() {
+        let mut array = ndarray::Array3::ones((2, 10, 10));
+        mask_outside_circle(&mut array, Point2::new(5., 5.), 5.);
+        for band in array.outer_iter() {
+            assert!(band[[0, 0]].is_nan());
+            assert!(band[[9, 9]].is_nan());
+            assert_eq!(band[[4, 4]], 1.);
+            assert_eq!(band[[0, 4]], 1.);
+        }
+    }
+
+    #[rstest]
+    fn read_bands_circle(test_raster: Raster<Sentinel2>) {
+        let footprint = test_raster.metadata.footprint().unwrap();
+        let geo::Geometry::Polygon(polygon) = footprint.geometry else {
+            panic!("footprint isn't a polygon");
+        };
+        let center = geo::Centroid::centroid(&polygon).unwrap();
+        let circle = test_raster
+            .read_bands_circle(vec!["B4", "B5"], center.x_y(), 100.)
+            .unwrap();
+        // 10 pixels of radius, the window is 20 or 21 pixels wide.
+        let (bands, width, height) = circle.dim();
+        assert_eq!(bands, 2);
+        assert!((20..=21).contains(&width) && (20..=21).contains(&height));
+        assert!(circle[[0, 0, 0]].is_nan());
+        assert!(circle[[1, width - 1, height - 1]].is_nan());
+        assert!(!circle[[0, 10, 10]].is_nan());
+        assert!(!circle[[1, 9, 9]].is_nan());
+    }
+
+    #[rstest]
+    fn read_bands_bbox(test_raster: Raster<Sentinel2>) {
+        let [_, _, c, _, _, f] = test_raster.profile().unwrap().transform;
+        let utm = test_raster
+            .read_bands_bbox(vec!["B4", "B5"], (c + 1000., f - 2000.), (c + 1500., f - 1000.), None)
+            .unwrap();
+        assert_eq!(utm, test_raster.read_bands(vec!["B4", "B5"], (100, 100), (50, 100)).unwrap());
+        // Hanging over the upper left corner of the scene.
+        let clipped = test_raster
+            .read_bands_bbox(vec!["B4"], (c - 500., f - 300.), (c + 200., f + 100.), None)
+            .unwrap();
+        assert_eq!(clipped.dim(), (1, 20, 30));
+
+        let (min_lon, min_lat, max_lon, max_lat) = test_raster.valid_extent_lonlat("B4").unwrap();
+        let (center_lon, center_lat) = ((min_lon + max_lon) / 2., (min_lat + max_lat) / 2.);
+        let lonlat = test_raster
+            .read_bands_bbox(
+                vec!["B4"],
+                (center_lon - 0.01, center_lat - 0.01),
+                (center_lon + 0.01, center_lat + 0.01),
+                Some("EPSG:4326"),
+            )
+            .unwrap();
+        // About 2.2 km across at these latitudes.
+        let (_, width, height) = lonlat.dim();
+        assert!((200..240).contains(&width) && (200..240).contains(&height));
+        assert!(matches!(
+            test_raster.read_bands_bbox(vec!["B4"], (0., 0.), (1., 1.), Some("EPSG:4326")),
+            Err(Sentinel2ArrayError::BboxOutsideRaster { .. })
+        ));
+    }
+
+    #[test]
+    fn resample_classes_majority() {
+        // 20 m classes onto a 60 m grid, the centre of the first 3x3 block is
+        // the odd one out.
+        let mut classes = Array2::from_elem((6, 6), 4u16);
+        classes[[1, 1]] = 8;
+        classes.slice_mut(ndarray::s![3.., ..]).fill(5);
+        let transform = nalgebra::Matrix3::new(3., 0., 0., 0., 3., 0., 0., 0., 1.);
+        let nearest = resample_classes(classes.view(), transform, (2, 2), ClassResample::Nearest);
+        let majority =
+            resample_classes(classes.view(), transform, (2, 2), ClassResample::Majority);
+        assert_eq!(nearest, ndarray::array![[8, 4], [5, 5]]);
+        assert_eq!(majority, ndarray::array![[4, 4], [5, 5]]);
+    }
+
+    #[rstest]
+    fn read_bands_labeled_with(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((10, 10), (32, 32));
+        let (_, nearest) = test_raster.read_bands_labeled(vec!["B4"], offset, window).unwrap();
+        // Upsampling, every read pixel lies within a single source pixel.
+        let (_, majority) = test_raster
+            .read_bands_labeled_with(vec!["B4"], offset, window, ClassResample::Majority)
+            .unwrap();
+        assert_eq!(nearest, majority);
+    }
+
+    #[rstest]
+    fn read_bands_to_grid_traced(test_raster: Raster<Sentinel2>) {
+        let raster_grid = test_raster.grid().unwrap();
+        let grid = Grid {
+            transform: raster_grid.transform
+                * nalgebra::Matrix3::new_translation(&nalgebra::Vector2::new(100., 100.)),
+            size: (32, 32),
+            ..raster_grid.clone()
+        };
+        let (array, chain) = test_raster.read_bands_to_grid_traced(vec!["B4"], &grid).unwrap();
+        assert_eq!(array.dim(), (1, 32, 32));
+        assert_eq!(chain.source_crs, raster_grid.crs);
+        assert_eq!(chain.target_crs, raster_grid.crs);
+        assert_eq!(chain.resampling, Resampling::Bilinear);
+        assert_eq!(chain.source_transform, raster_grid.transform);
+        let composed = chain.composed.unwrap();
+        assert_eq!((composed.m13, composed.m23), (100., 100.));
+
+        let wgs84 = gdal::spatial_ref::SpatialRef::from_epsg(4326).unwrap().to_wkt().unwrap();
+        let geographic = Grid {
+            crs: wgs84.clone(),
+            transform: nalgebra::Matrix3::new(1e-4, 0., 14.5, 0., -1e-4, 14.5, 0., 0., 1.),
+            size: (8, 8),
+        };
+        let (_, chain) = test_raster
+            .read_bands_to_grid_traced(vec!["B4"], &geographic)
+            .unwrap();
+        assert_eq!(chain.source_crs, raster_grid.crs);
+        assert_eq!(chain.target_crs, wgs84);
+        assert!(chain.composed.is_none());
+        assert_eq!(chain.to_map()["composed"], "None");
+    }
+
+    #[rstest]
+    fn world_file_string(test_raster: Raster<Sentinel2>) {
+        let [a, b, c, d, e, f] = test_raster.profile().unwrap().transform;
+        let world_file = test_raster.world_file_string((100, 200)).unwrap();
+        let lines = world_file
+            .lines()
+            .map(|line| line.parse::<f64>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines[..4], [a, d, b, e]);
+        assert_eq!(lines[4], a * 100.5 + b * 200.5 + c);
+        assert_eq!(lines[5], d * 100.5 + e * 200.5 + f);
+    }
+
+    #[test]
+    fn split_patches_edges() {
+        let array =
+            ndarray::Array3::from_shape_fn((2, 10, 8), |(c, x, y)| (c * 100 + x * 10 + y) as u16);
+        let dropped = split_patches(&array, (4, 4), (3, 3), EdgePatches::Drop);
+        // Origins 0, 3, 6 along x and 0, 3 along y.
+        assert_eq!(dropped.len(), 6);
+        assert!(dropped.iter().all(|patch| patch.dim() == (2, 4, 4)));
+        assert_eq!(dropped[1][[1, 0, 0]], array[[1, 3, 0]]);
+        assert_eq!(dropped[3][[0, 1, 2]], array[[0, 1, 5]]);
+
+        // Origins 0, 3, 6 along x and 0, 3, 6 along y, the last row padded.
+        let padded = split_patches(&array, (4, 4), (3, 3), EdgePatches::Pad);
+        assert_eq!(padded.len(), 9);
+        assert!(padded.iter().all(|patch| patch.dim() == (2, 4, 4)));
+        assert_eq!(padded[8][[1, 3, 0]], array[[1, 9, 6]]);
+        assert_eq!(padded[8][[1, 3, 1]], array[[1, 9, 7]]);
+        assert_eq!(padded[8][[1, 3, 2]], 0);
+    }
+
+    #[rstest]
+    fn read_patches(test_raster: Raster<Sentinel2>) {
+        let patches = test_raster
+            .read_patches(vec!["B4", "B5"], (0, 0), (64, 48), (32, 32), (16, 16), EdgePatches::Drop)
+            .unwrap();
+        assert_eq!(patches.len(), 3 * 2);
+        let array = test_raster.read_bands(vec!["B4", "B5"], (16, 16), (32, 32)).unwrap();
+        assert_eq!(patches[4], array);
+    }
+
+    #[rstest]
+    fn read_bands_gdal(test_raster: Raster<Sentinel2>) {
+        let band_names = vec!["B4", "B5", "B1"];
+        // Edges on whole pixels of the 10, 20 and 60 m grids.
+        let (offset, window) = ((120, 60), (96, 72));
+        assert_eq!(
+            test_raster.read_bands_gdal(band_names.clone(), offset, window).unwrap(),
+            test_raster.read_bands(band_names.clone(), offset, window).unwrap()
+        );
+        // Off the 20 m grid, only the 10 m band is guaranteed to match.
+        let (offset, window) = ((11, 11), (31, 31));
+        let gdal = test_raster.read_bands_gdal(band_names.clone(), offset, window).unwrap();
+        let rust = test_raster.read_bands(band_names, offset, window).unwrap();
+        assert_eq!(gdal.index_axis(Axis(0), 0), rust.index_axis(Axis(0), 0));
+        assert_eq!(gdal.dim(), rust.dim());
+    }
+
+    #[rstest]
+    fn group_count(test_raster: Raster<Sentinel2>) {
+        // The 10, 20 and 60 m subdatasets.
+        assert_eq!(test_raster.group_count(), 3);
+        assert!(test_raster.group_count() < test_raster.band_names().len());
+    }
+
+    #[rstest]
+    fn read_bands_coords(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((100, 200), (30, 20));
+        let (array, xs, ys) = test_raster
+            .read_bands_coords(vec!["B4", "B5"], offset, window)
+            .unwrap();
+        assert_eq!(array.dim(), (2, 30, 20));
+        assert_eq!((xs.len(), ys.len()), window);
+        let [a, _, c, _, e, f] = test_raster.profile().unwrap().transform;
+        assert_eq!(xs[0], c + a * 100.5);
+        assert_eq!(ys[0], f + e * 200.5);
+        assert_eq!(xs[1] - xs[0], a);
+    }
+
+    #[rstest]
+    fn read_bands_runtime_names(test_raster: Raster<Sentinel2>) {
+        let band_names = (2..=4).map(|band| format!("B{band}")).collect::<Vec<String>>();
+        let (offset, window) = ((0, 0), (16, 16));
+        assert_eq!(
+            test_raster.read_bands(&band_names, offset, window).unwrap(),
+            test_raster.read_bands(vec!["B2", "B3", "B4"], offset, window).unwrap()
+        );
+        let missing = vec![String::from("B4"), String::from("B42")];
+        assert!(matches!(
+            test_raster.read_bands(missing, offset, window),
+            Err(Sentinel2ArrayError::BandNotFound(band_name)) if band_name == "B42"
+        ));
+        assert_eq!(
+            test_raster.read().bands(&band_names).window(offset, window).execute().unwrap(),
+            test_raster.read_bands(&band_names, offset, window).unwrap()
+        );
+        assert_eq!(
+            test_raster.read_bands_reflectance_masked(&band_names, offset, window).unwrap().dim(),
+            (3, 16, 16)
+        );
+        let (reflectance, _) = test_raster.read_bands_labeled(&band_names, offset, window).unwrap();
+        assert_eq!(reflectance.dim(), (3, 16, 16));
+    }
+
+    #[test]
+    fn read_bands_partial() {
+        let band_group = |name: &str| {
+            let geo_transform = [0., 10., 0., 0., 0., -10.];
+            let (dataset, path) =
+                gtiff_dataset(&format!("read_bands_partial_{name}"), geo_transform, (4, 4), 1);
+            dataset.rasterband(1).unwrap().fill(7., None).unwrap();
+            (Arc::new(BandGroup::new(&dataset).unwrap()), path)
+        };
+        let (good, good_path) = band_group("good");
+        let (bad, bad_path) = band_group("bad");
+        // The band's file is gone by the time it's read.
+        std::fs::remove_file(bad_path).unwrap();
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([
+                ("GOOD".to_string(), BandInfo::new(good, 1, ())),
+                ("BAD".to_string(), BandInfo::new(bad, 1, ())),
+            ]),
+            (),
+        );
+
+        let (offset, window) = ((0, 0), (4, 4));
+        assert!(raster
+            .read_bands_partial(vec!["GOOD", "BAD"], offset, window, PartialPolicy::FailFast)
+            .is_err());
+        let PartialRead { array, failed } = raster
+            .read_bands_partial(vec!["GOOD", "BAD"], offset, window, PartialPolicy::BestEffort)
+            .unwrap();
+        assert!(array.index_axis(Axis(0), 0).iter().all(|&value| value == 7));
+        assert!(array.index_axis(Axis(0), 1).iter().all(|&value| value == 0));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "BAD");
+        assert!(matches!(
+            raster.read_bands_partial(vec!["GOOD"], (2, 0), window, PartialPolicy::BestEffort),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+        std::fs::remove_file(good_path).unwrap();
+    }
+
+    #[test]
+    fn build_overviews() {
+        let (band_group, path) = {
+            let (dataset, path) =
+                gtiff_dataset("build_overviews", [0., 10., 0., 0., 0., -10.], (64, 64), 2);
+            dataset.rasterband(1).unwrap().fill(7., None).unwrap();
+            dataset.rasterband(2).unwrap().fill(9., None).unwrap();
+            (Arc::new(BandGroup::new(&dataset).unwrap()), path)
+        };
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([
+                ("A".to_string(), BandInfo::new(Arc::clone(&band_group), 1, ())),
+                ("B".to_string(), BandInfo::new(band_group, 2, ())),
+            ]),
+            (),
+        );
+        assert_eq!(raster.band_overview_count("A").unwrap(), 0);
+        raster.build_overviews(&[2, 4, 8], Resampling::Nearest).unwrap();
+        assert_eq!(raster.band_overview_count("A").unwrap(), 3);
+        assert_eq!(raster.band_overview_count("B").unwrap(), 3);
+        let thumbnail = raster.read_thumbnail(vec!["A", "B"], 8).unwrap();
+        assert_eq!(thumbnail.dim(), (2, 8, 8));
+        assert!(thumbnail.index_axis(Axis(0), 1).iter().all(|&value| value == 9));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn build_overviews_zipped(test_raster: Raster<Sentinel2>) {
+        assert!(matches!(
+            test_raster.build_overviews(&[2], Resampling::Nearest),
+            Err(Sentinel2ArrayError::OverviewsNotWritable(_))
+        ));
+    }
+
+    #[test]
+    fn validate_alignment() {
+        let band = |name: &str, geo_transform: [f64; 6]| {
+            let (band_group, path) =
+                gtiff_band_group(&format!("validate_alignment_{name}"), geo_transform, (4, 4), 1);
+            std::fs::remove_file(path).unwrap();
+            (name.to_string(), BandInfo::new(band_group, 1, ()))
+        };
+        let a = band("A", [0., 10., 0., 0., 0., -10.]);
+        // Starting further along an aligned grid, and at another resolution.
+        let b = band("B", [20., 10., 0., -40., 0., -10.]);
+        let c = band("C", [5., 20., 0., 0., 0., -20.]);
+        // A third of a pixel off the grid of A.
+        let d = band("D", [10. / 3., 10., 0., 0., 0., -10.]);
+
+        let aligned = Raster::<Reference>::new(Bands::from_iter([a.clone(), b, c]), ());
+        aligned.validate_alignment().unwrap();
+        let misaligned = Raster::<Reference>::new(Bands::from_iter([a, d]), ());
+        match misaligned.validate_alignment() {
+            Err(Sentinel2ArrayError::MisalignedBands { band, reference, offset }) => {
+                assert_eq!((band.as_str(), reference.as_str()), ("D", "A"));
+                assert!((offset.0 - 1. / 3.).abs() < 1e-9 && offset.1 == 0.);
+            }
+            other => panic!("expected MisalignedBands, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn read_band_transform_not_invertible() {
+        // Both pixel axes along the same direction.
+        let geo_transform = [0., 10., 10., 0., 1., 1.];
+        let (band_group, path) =
+            gtiff_band_group("read_band_transform_not_invertible", geo_transform, (4, 4), 1);
+        // Warping skips the rejection of rotated grids.
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([("A".to_string(), BandInfo::new(band_group, 1, ()))]),
+            (),
+        )
+        .with_band_mismatch(BandMismatch::Warp);
+        assert!(matches!(
+            raster.read_band("A", (0, 0), (2, 2)),
+            Err(Sentinel2ArrayError::BandTransformNotInvertible(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn valid_extent_lonlat() {
+        // A 100x80 pixels band with a 10 pixels border of no data, and a full one.
+        let mut bordered = Array2::zeros((100, 80));
+        bordered.slice_mut(ndarray::s![10..90, 10..70]).fill(1u16);
+        assert_eq!(valid_window(bordered.view(), 0), Some(((10, 10), (80, 60))));
+        assert_eq!(valid_window(Array2::zeros((3, 3)).view(), 0), None);
+
+        let geo_transform = [300000., 60., 0., 1600020., 0., -60.];
+        let (dataset, path) = gtiff_dataset("valid_extent_lonlat", geo_transform, (100, 80), 2);
+        // GDAL buffers are row major.
+        let data = bordered.t().iter().copied().collect::<Vec<_>>();
+        let mut buffer = gdal::raster::Buffer::new((100, 80), data);
+        dataset.rasterband(1).unwrap().write((0, 0), (100, 80), &mut buffer).unwrap();
+        dataset.rasterband(2).unwrap().fill(1., None).unwrap();
+        let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+        drop(dataset);
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([
+                ("BORDERED".to_string(), BandInfo::new(Arc::clone(&band_group), 1, ())),
+                ("FULL".to_string(), BandInfo::new(band_group, 2, ())),
+            ]),
+            (),
+        );
+
+        let (min_lon, min_lat, max_lon, max_lat) = raster.valid_extent_lonlat("BORDERED").unwrap();
+        let full = raster.valid_extent_lonlat("FULL").unwrap();
+        assert!(full.0 < min_lon && full.1 < min_lat && max_lon < full.2 && max_lat < full.3);
+        assert!((12. ..15.).contains(&min_lon) && (14. ..15.).contains(&min_lat));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn read_bands_flush_with_edge(test_raster: Raster<Sentinel2>) {
+        let (width, height) = test_raster.size().unwrap();
+        for window in [(64, 64), (63, 61), (1, 1)] {
+            let offset = ((width - window.0) as isize, (height - window.1) as isize);
+            let array = test_raster
+                .read_bands(vec!["B4", "B11", "B12", "B1"], offset, window)
+                .unwrap();
+            assert_eq!(array.dim(), (4, window.0, window.1));
+            // The last read pixel is the last pixel of the 20 m band.
+            let last = ((width / 2 - 1) as isize, (height / 2 - 1) as isize);
+            let corner = test_raster.read_band_source_window("B11", last, (1, 1)).unwrap();
+            assert_eq!(array[[1, window.0 - 1, window.1 - 1]], corner[[0, 0]]);
+        }
+    }
+
+    #[test]
+    fn read_band_diagnostic() {
+        // A 20x20 pixels band, of no data on its left half.
+        let geo_transform = [300000., 10., 0., 1600000., 0., -10.];
+        let (dataset, path) = gtiff_dataset("read_band_diagnostic", geo_transform, (20, 20), 1);
+        let mut band = dataset.rasterband(1).unwrap();
+        band.set_no_data_value(Some(0.)).unwrap();
+        let data = (0..400).map(|index| if index % 20 < 10 { 0 } else { 5 }).collect();
+        band.write((0, 0), (20, 20), &mut gdal::raster::Buffer::new((20, 20), data)).unwrap();
+        let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+        drop(dataset);
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([("B1".to_string(), BandInfo::new(band_group, 1, ()))]),
+            (),
+        );
+
+        // Hanging 5 pixels past the right edge.
+        let statuses = raster.read_band_diagnostic("B1", (5, 8), (20, 4)).unwrap();
+        for ((x, _), &status) in statuses.indexed_iter() {
+            let expected = match x {
+                0..5 => PixelStatus::NoData,
+                5..15 => PixelStatus::Valid(5),
+                _ => PixelStatus::OutOfBounds,
+            };
+            assert_eq!(status, expected);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn raster_options() {
+        let subdataset = |name: &str| Subdataset {
+            name: name.into(),
+            description: String::new(),
+        };
+        let tci = subdataset("SENTINEL2_L2A:/MTD_MSIL2A.xml:TCI:EPSG_32633");
+        let bands = subdataset("SENTINEL2_L2A:/MTD_MSIL2A.xml:10m:EPSG_32633");
+        let default = RasterOptions::default();
+        assert!(!default.includes(&tci) && default.includes(&bands));
+        let with_tci = RasterOptions {
+            include_tci: true,
+            ..Default::default()
+        };
+        assert!(with_tci.includes(&tci) && with_tci.includes(&bands));
+
+        let excluded = Sentinel2::raster_from(TEST_DATA).unwrap();
+        let included = Sentinel2::raster_from_with_options(TEST_DATA, &with_tci).unwrap();
+        assert_eq!(excluded.group_count(), 3);
+        assert_eq!(included.group_count(), 4);
+    }
+
+    #[rstest]
+    fn scanlines(test_raster: Raster<Sentinel2>) {
+        // Over two blocks, the last one partial.
+        let (offset, window) = ((30, 45), (50, 70));
+        let scanlines = test_raster
+            .scanlines(vec!["B4", "B5", "B1"], offset, window)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(scanlines.len(), 70);
+        assert!(scanlines.iter().all(|scanline| scanline.dim() == (3, 50)));
+        let views = scanlines.iter().map(Array2::view).collect::<Vec<_>>();
+        assert_eq!(
+            ndarray::stack(Axis(2), &views).unwrap(),
+            test_raster.read_bands(vec!["B4", "B5", "B1"], offset, window).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn read_band_lut(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((0, 0), (32, 32));
+        let mut lut = Box::new([0u16; 65536]);
+        lut.iter_mut()
+            .enumerate()
+            .for_each(|(value, recoded)| *recoded = u16::MAX - value as u16);
+        let recoded = test_raster.read_band_lut("B5", offset, window, &lut).unwrap();
+        let band = test_raster.read_bands(vec!["B5"], offset, window).unwrap();
+        assert_eq!(recoded, band.index_axis(Axis(0), 0).mapv(|value| u16::MAX - value));
+    }
+
+    #[test]
+    fn read_bands_multiple_projections() {
+        let wkt = |epsg| gdal::spatial_ref::SpatialRef::from_epsg(epsg).unwrap().to_wkt().unwrap();
+        let band_group = |name: &str, crs: &str, geo_transform: [f64; 6], size, value: f64| {
+            let (mut dataset, path) =
+                gtiff_dataset(&format!("projections_{name}"), geo_transform, (size, size), 1);
+            dataset.set_projection(crs).unwrap();
+            dataset.rasterband(1).unwrap().fill(value, None).unwrap();
+            (Arc::new(BandGroup::new(&dataset).unwrap()), path)
+        };
+        // 10 m pixels in UTM 33N, and 20 m ones over the same area in UTM 34N.
+        let (fine, fine_path) =
+            band_group("fine", &wkt(32633), [800000., 10., 0., 1600000., 0., -10.], 40, 1.);
+        let mut corner = ([800000.], [1600000.]);
+        let mut from = gdal::spatial_ref::SpatialRef::from_epsg(32633).unwrap();
+        let mut to = gdal::spatial_ref::SpatialRef::from_epsg(32634).unwrap();
+        from.set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
+        to.set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
+        gdal::spatial_ref::CoordTransform::new(&from, &to)
+            .unwrap()
+            .transform_coords(&mut corner.0, &mut corner.1, &mut [0.])
+            .unwrap();
+        let coarse_transform = [corner.0[0] - 100., 20., 0., corner.1[0] + 100., 0., -20.];
+        let (coarse, coarse_path) =
+            band_group("coarse", &wkt(32634), coarse_transform, 30, 42.);
+        let bands = Bands::from_iter([
+            ("FINE".to_string(), BandInfo::new(fine, 1, ())),
+            ("COARSE".to_string(), BandInfo::new(coarse, 1, ())),
+        ]);
+        assert!(!bands.share_projection().unwrap());
+        let raster = Raster::<Reference>::new(bands, ());
+        assert!(matches!(
+            raster.read_bands(vec!["FINE", "COARSE"], (0, 0), (40, 40)),
+            Err(Sentinel2ArrayError::IncompatibleBandGroups(band_names)) if band_names == ["COARSE"]
+        ));
+        assert!(matches!(
+            raster.read().bands(vec!["FINE", "COARSE"]).window((0, 0), (40, 40)).execute(),
+            Err(Sentinel2ArrayError::IncompatibleBandGroups(_))
+        ));
+        assert!(matches!(
+            raster.read_bands_resampled(
+                vec!["FINE", "COARSE"],
+                (0, 0),
+                (40, 40),
+                Resampling::Bilinear
+            ),
+            Err(Sentinel2ArrayError::IncompatibleBandGroups(_))
+        ));
+        assert!(matches!(
+            raster.read_bands_partial(
+                vec!["FINE", "COARSE"],
+                (0, 0),
+                (40, 40),
+                PartialPolicy::BestEffort
+            ),
+            Err(Sentinel2ArrayError::IncompatibleBandGroups(_))
+        ));
+        assert!(raster.read_bands(vec!["COARSE"], (0, 0), (20, 20)).is_ok());
+
+        let raster = raster.with_band_mismatch(BandMismatch::Warp);
+        let array = raster.read_bands(vec!["FINE", "COARSE"], (0, 0), (40, 40)).unwrap();
+        assert!(array.index_axis(Axis(0), 0).iter().all(|&value| value == 1));
+        // Warped into UTM 33N, well within the coarse band.
+        assert_eq!(array[[1, 20, 20]], 42);
+        std::fs::remove_file(fine_path).unwrap();
+        std::fs::remove_file(coarse_path).unwrap();
+    }
+
+    #[rstest]
+    fn read_band(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((17, 23), (40, 30));
+        let band = test_raster.read_band("B8", offset, window).unwrap();
+        let bands = test_raster.read_bands(vec!["B8"], offset, window).unwrap();
+        assert_eq!(band, bands.index_axis(Axis(0), 0));
+        assert!(matches!(
+            test_raster.read_band("B42", offset, window),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_cast(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((300, 400), (25, 15));
+        let u16_array = test_raster.read_bands(vec!["B4", "B12"], offset, window).unwrap();
+        let f32_array = test_raster
+            .read_bands_cast::<f32, _>(vec!["B4", "B12"], offset, window)
+            .unwrap();
+        assert_eq!(f32_array, u16_array.mapv(f32::from));
+        assert_eq!(
+            test_raster.read_bands_cast::<u16, _>(vec!["B4", "B12"], offset, window).unwrap(),
+            u16_array
+        );
+    }
+
+    #[rstest]
+    fn read_bands_typed(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((64, 32), (12, 12));
+        assert_eq!(
+            test_raster
+                .read_bands_typed(&[S2Band::B8A, S2Band::B2, S2Band::Scl], offset, window)
+                .unwrap(),
+            test_raster.read_bands(vec!["B8A", "B2", "SCL"], offset, window).unwrap()
+        );
+        assert_eq!(S2Band::Wvp.to_string(), "WVP");
+    }
+
+    #[rstest]
+    fn band_resolution_precise(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.band_resolution_precise("B4").unwrap(), 10.0);
+        assert_eq!(test_raster.band_resolution_precise("B8A").unwrap(), 20.0);
+        assert!(matches!(
+            test_raster.band_resolution_precise("B42"),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn raster_from_vsizip(test_raster: Raster<Sentinel2>) {
+        let zip_path = std::fs::canonicalize(TEST_DATA).unwrap();
+        let safe_name = zip_path.file_stem().unwrap().to_str().unwrap();
+        let vsi_path = format!("/vsizip/{}/{safe_name}/MTD_MSIL2A.xml", zip_path.display());
+        let raster = Sentinel2::raster_from(&vsi_path).unwrap();
+        assert_eq!(raster.band_names(), test_raster.band_names());
+        let (offset, window) = ((5000, 5000), (16, 16));
+        assert_eq!(
+            raster.read_bands(vec!["B4", "B11"], offset, window).unwrap(),
+            test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn raster_from_safe_dir(test_raster: Raster<Sentinel2>) {
+        let extract_dir = std::env::temp_dir().join("rusterio_raster_from_safe_dir");
+        if !extract_dir.exists() {
+            let mut archive =
+                zip::ZipArchive::new(std::fs::File::open(TEST_DATA).unwrap()).unwrap();
+            archive.extract(&extract_dir).unwrap();
+        }
+        let safe_name = Path::new(TEST_DATA).file_stem().unwrap();
+        let safe_dir = extract_dir.join(safe_name);
+        for path in [safe_dir.clone(), safe_dir.join("MTD_MSIL2A.xml")] {
+            let raster = Sentinel2::raster_from(&path).unwrap();
+            assert_eq!(raster.band_names(), test_raster.band_names());
+        }
+    }
+
+    #[rstest]
+    fn acquisition(test_raster: Raster<Sentinel2>) {
+        let acquisition = test_raster.acquisition().unwrap();
+        assert_eq!(
+            acquisition.sensing_time.date_naive(),
+            chrono::NaiveDate::from_ymd_opt(2024, 11, 26).unwrap()
+        );
+        assert_eq!(acquisition.sensing_time.format("%H%M").to_string(), "0932");
+        assert_eq!(acquisition.tile_id, "T33PTM");
+        assert_eq!(acquisition.relative_orbit, 136);
+        assert_eq!(acquisition.processing_baseline, "05.11");
+    }
+
+    #[rstest]
+    fn read_bands_reproject(test_raster: Raster<Sentinel2>) {
+        let (array, transform) = test_raster
+            .read_bands_reproject(vec!["B4", "B11"], (5000, 5000), (32, 32), "EPSG:4326")
+            .unwrap();
+        assert_eq!(array.dim().0, 2);
+        // Degrees: about 1e-4 per 10 m pixel, origin within lon/lat bounds.
+        assert!(transform.m11 > 0. && transform.m11 < 1e-3);
+        assert!(transform.m22 < 0. && transform.m22 > -1e-3);
+        assert!((-180. ..=180.).contains(&transform.m13));
+        assert!((-90. ..=90.).contains(&transform.m23));
+        assert!(array.iter().any(|&value| value != 0));
+    }
+
+    #[rstest]
+    fn reader_cache(test_raster: Raster<Sentinel2>) {
+        let first = test_raster.read_bands(vec!["B4", "B11"], (0, 0), (16, 16)).unwrap();
+        for step in 1..100 {
+            let offset = (step * 16, step * 16);
+            test_raster.read_bands(vec!["B4", "B11"], offset, (16, 16)).unwrap();
+        }
+        // One dataset per band group, not per read.
+        assert_eq!(test_raster.datasets_opened(), 2);
+        test_raster.clear_cache();
+        let reread = test_raster.read_bands(vec!["B4", "B11"], (0, 0), (16, 16)).unwrap();
+        assert_eq!(test_raster.datasets_opened(), 4);
+        assert_eq!(reread, first);
+    }
+
+    #[rstest]
+    fn reader_cache_shared_by_group(test_raster: Raster<Sentinel2>) {
+        // B4 and B3 are bands of the same 10 m subdataset.
+        test_raster.read_band("B4", (0, 0), (16, 16)).unwrap();
+        test_raster.read_band("B3", (16, 16), (16, 16)).unwrap();
+        assert_eq!(test_raster.datasets_opened(), 1);
+    }
+
+    #[rstest]
+    fn clone_raster(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((3000, 3000), (24, 24));
+        let expected = test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap();
+        let cloned = test_raster.clone();
+        drop(test_raster);
+        let handles = [cloned.clone(), cloned].map(|raster| {
+            thread::spawn(move || raster.read_bands(vec!["B4", "B11"], offset, window).unwrap())
+        });
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+
+    #[rstest]
+    fn band_for_wavelength(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.band_for_wavelength(842.).unwrap(), "B8");
+        assert_eq!(test_raster.band_for_wavelength(1610.).unwrap(), "B11");
+        assert!(matches!(
+            test_raster.band_for_wavelength(3000.),
+            Err(Sentinel2ArrayError::NoBandNearWavelength { .. })
+        ));
+        let (offset, window) = ((100, 100), (8, 8));
+        assert_eq!(
+            test_raster.read_bands_by_wavelength(&[665., 842.], offset, window).unwrap(),
+            test_raster.read_bands(vec!["B4", "B8"], offset, window).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn normalized_difference(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((4000, 4000), (16, 16));
+        let reflectance = test_raster
+            .read_bands_reflectance(vec!["B8", "B4"], offset, window)
+            .unwrap();
+        let (nir, red) = (reflectance.index_axis(Axis(0), 0), reflectance.index_axis(Axis(0), 1));
+        let expected = (&nir - &red) / (&nir + &red);
+        let ndvi = test_raster.ndvi(offset, window).unwrap();
+        assert!(ndvi.iter().zip(&expected).all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+        assert_eq!(
+            test_raster
+                .normalized_difference_with_fill("B4", "B4", offset, window, -2.)
+                .unwrap()
+                .iter()
+                .filter(|&&value| value == -2.)
+                .count(),
+            red.iter().filter(|&&value| value == 0.).count()
+        );
+    }
+
+    #[rstest]
+    fn tiles(test_raster: Raster<Sentinel2>) {
+        let size = (25, 17);
+        let windows = tile_windows(size, (10, 8)).collect::<Vec<_>>();
+        assert_eq!(windows.len(), 9);
+        assert_eq!(windows[2], ((20, 0), (5, 8)));
+        assert_eq!(windows[8], ((20, 16), (5, 1)));
+        let mut covered = Array2::<u8>::zeros(size);
+        for ((x, y), (width, height)) in windows {
+            covered
+                .slice_mut(s![x..x + width as isize, y..y + height as isize])
+                .mapv_inplace(|count| count + 1);
+        }
+        assert!(covered.iter().all(|&count| count == 1));
+
+        let (width, height) = test_raster.size().unwrap();
+        let tiles = test_raster.tiles((4096, 4096)).unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            tiles.iter().map(|(_, (w, h))| w * h).sum::<usize>(),
+            width * height
+        );
+        let ((x, y), (w, h)) = *tiles.last().unwrap();
+        assert_eq!((x as usize + w, y as usize + h), (width, height));
+    }
+
+    #[test]
+    fn clip_window_edges() {
+        let size = (100, 80);
+        assert_eq!(clip_window((10, 10), (20, 20), size), Some(((10, 10), (20, 20))));
+        assert_eq!(clip_window((-5, 70), (20, 20), size), Some(((0, 70), (15, 10))));
+        assert_eq!(clip_window((100, 0), (20, 20), size), None);
+        assert_eq!(clip_window((-30, 0), (20, 20), size), None);
+    }
+
+    #[rstest]
+    fn read_bands_out_of_bounds(test_raster: Raster<Sentinel2>) {
+        let (width, height) = test_raster.size().unwrap();
+        let outside = ((width as isize + 10, 0), (16, 16));
+        let partial = ((width as isize - 6, height as isize - 4), (16, 16));
+        for (offset, window) in [outside, partial] {
+            assert!(matches!(
+                test_raster.read_bands(vec!["B4", "B11"], offset, window),
+                Err(Sentinel2ArrayError::WindowOutOfBounds { raster_size, .. })
+                    if raster_size == (width, height)
+            ));
+        }
+        assert!(matches!(
+            test_raster.read_bands_clamped(vec!["B4", "B11"], outside.0, outside.1),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+        let (array, (offset, window)) = test_raster
+            .read_bands_clamped(vec!["B4", "B11"], partial.0, partial.1)
+            .unwrap();
+        assert_eq!((offset, window), (partial.0, (6, 4)));
+        assert_eq!(array, test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap());
+    }
+
+    #[rstest]
+    fn read_bands_filled(test_raster: Raster<Sentinel2>) {
+        let (_, height) = test_raster.size().unwrap();
+        // One row past the bottom edge.
+        let (offset, window) = ((3000, height as isize - 7), (12, 8));
+        let array = test_raster.read_bands_filled(vec!["B4", "B11"], offset, window, 9999).unwrap();
+        assert!(array.slice(s![.., .., 7]).iter().all(|&value| value == 9999));
+        assert_eq!(
+            array.slice(s![.., .., ..7]),
+            test_raster.read_bands(vec!["B4", "B11"], offset, (12, 7)).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn read_bands_georef(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((300, 200), (8, 8));
+        let (array, transform) = test_raster
+            .read_bands_georef(vec!["B4", "B11"], offset, window)
+            .unwrap();
+        assert_eq!(array, test_raster.read_bands(vec!["B4", "B11"], offset, window).unwrap());
+        let grid = test_raster.band_geo_transform("B4").unwrap();
+        let origin = transform.transform_point(&Point2::new(0., 0.));
+        assert_eq!(origin.x, grid[0] + 300. * grid[1]);
+        assert_eq!(origin.y, grid[3] + 200. * grid[5]);
+    }
+
+    #[rstest]
+    fn cloud_coverage(test_raster: Raster<Sentinel2>) {
+        assert!((0. ..=100.).contains(&test_raster.cloud_coverage().unwrap()));
+        assert!((0. ..=100.).contains(&test_raster.nodata_coverage().unwrap()));
+        let stripped = Raster::<Sentinel2>::new(
+            Bands::default(),
+            sentinel2::RasterMetadata::new("stripped".into()),
+        );
+        assert!(matches!(
+            stripped.cloud_coverage(),
+            Err(Sentinel2ArrayError::MetadataKeyNotFound { key, .. })
+                if key == "CLOUD_COVERAGE_ASSESSMENT"
+        ));
+    }
+
+    #[rstest]
+    fn product_level(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.product_level().unwrap(), ProductLevel::L2A);
+
+        let mut metadata = Metadata::new("level-1C product".into());
+        metadata.insert("PRODUCT_TYPE".into(), "S2MSI1C".into());
+        metadata.insert("QUANTIFICATION_VALUE".into(), "10000".into());
+        let level_1c = Raster::<Sentinel2>::new(Bands::default(), metadata.into());
+        assert_eq!(level_1c.product_level().unwrap(), ProductLevel::L1C);
+        assert!(matches!(
+            level_1c.read_scl((0, 0), (4, 4)),
+            Err(Sentinel2ArrayError::NotLevel2A(_))
+        ));
+        assert!(matches!(
+            level_1c.metadata.atmospheric_parameters(),
+            Err(Sentinel2ArrayError::NotLevel2A(_))
+        ));
+
+        // Level-1C bands may go without a `BANDNAME`.
+        let dataset = gdal::DriverManager::get_driver_by_name("MEM")
+            .unwrap()
+            .create_with_band_type::<u16, _>("", 4, 4, 1)
+            .unwrap();
+        let mut raster_band = dataset.rasterband(1).unwrap();
+        raster_band.set_description("B8A, central wavelength 865 nm").unwrap();
+        let (band_name, _) = Sentinel2::parse_rasterband_metadata(raster_band).unwrap();
+        assert_eq!(band_name, "B8A");
+    }
+
+    #[test]
+    fn stretch_bounds() {
+        let band = Array2::from_shape_fn((10, 10), |(x, y)| (10 * y + x) as u16);
+        assert_eq!(Stretch::MinMax.bounds(band.view()), (0., 99.));
+        assert_eq!(Stretch::Percentile(2., 98.).bounds(band.view()), (2., 97.));
+    }
+
+    #[cfg(feature = "image")]
+    #[rstest]
+    fn to_rgb_image(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((2000, 3000), (40, 30));
+        let image = test_raster.to_rgb_image(offset, window, Stretch::MinMax).unwrap();
+        assert_eq!(image.dimensions(), (40, 30));
+        let red = test_raster.read_band("B4", offset, window).unwrap();
+        let ((x, y), _) = red.indexed_iter().max_by_key(|(_, &value)| value).unwrap();
+        assert_eq!(image.get_pixel(x as u32, y as u32)[0], 255);
+    }
+
+    #[rstest]
+    fn read_builder_combinations(test_raster: Raster<Sentinel2>) {
+        let grid = test_raster.band_geo_transform("B4").unwrap();
+        let (x, y) = (grid[0] + 1000., grid[3] - 2000.);
+        let bbox = test_raster
+            .read()
+            .bands(vec!["B4", "B11"])
+            // Inset by a meter of the pixel edges to stay clear of rounding.
+            .bbox((x + 1., y - 239.), (x + 319., y - 1.), None)
+            .resampling(Resampling::Bilinear)
+            .fill(9999)
+            .execute_georef()
+            .unwrap();
+        assert_eq!(bbox.band_names, vec!["B4", "B11"]);
+        assert_eq!(bbox.array.dim(), (2, 32, 24));
+        let origin = bbox.transform.transform_point(&Point2::new(0., 0.));
+        assert_eq!((origin.x, origin.y), (x, y));
+        assert_eq!(
+            bbox.array,
+            test_raster
+                .read()
+                .bands(vec!["B4", "B11"])
+                .window((100, 200), (32, 24))
+                .resampling(Resampling::Bilinear)
+                .execute()
+                .unwrap()
+        );
+
+        // Filling past the bottom right corner.
+        let (width, height) = test_raster.size().unwrap();
+        let offset = (width as isize - 4, height as isize - 4);
+        let filled = test_raster
+            .read()
+            .bands(vec!["B4"])
+            .window(offset, (8, 8))
+            .fill(9999)
+            .execute()
+            .unwrap();
+        for ((_, x, y), &value) in filled.indexed_iter() {
+            assert_eq!(value == 9999, x >= 4 || y >= 4);
+        }
+
+        let reflectance = test_raster
+            .read()
+            .bands(vec!["B4", "B2"])
+            .window((500, 500), (8, 8))
+            .execute_reflectance()
+            .unwrap();
+        assert_eq!(
+            reflectance.array,
+            test_raster.read_bands_reflectance(vec!["B4", "B2"], (500, 500), (8, 8)).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn band_resolution(test_raster: Raster<Sentinel2>) {
+        for (band_name, resolution) in [("B2", 10), ("B5", 20), ("B1", 60)] {
+            assert_eq!(test_raster.band_resolution(band_name).unwrap(), resolution);
+        }
+        assert!(matches!(
+            test_raster.band_resolution("B99"),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+        assert!(test_raster.band_metadata("B4").unwrap().solar_irradiance().unwrap() > 0.);
+        assert!(matches!(
+            test_raster.band_metadata("B99"),
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn sample(test_raster: Raster<Sentinel2>) {
+        let band_names = ["B4", "B11", "B1"];
+        let grid = test_raster.band_geo_transform("B4").unwrap();
+        // Centre of pixel (1234, 567).
+        let point = (grid[0] + 1234.5 * grid[1], grid[3] + 567.5 * grid[5]);
+        let values = test_raster.sample(&band_names, point, None).unwrap();
+        let window = test_raster.read_bands(band_names, (1234, 567), (1, 1)).unwrap();
+        assert_eq!(values, window.into_iter().collect::<Vec<_>>());
+
+        let (min_lon, min_lat, _, _) = test_raster.valid_extent_lonlat("B4").unwrap();
+        let lonlat = (min_lon + 0.05, min_lat + 0.05);
+        assert_eq!(test_raster.sample(&band_names, lonlat, Some("EPSG:4326")).unwrap().len(), 3);
+        assert!(matches!(
+            test_raster.sample(&band_names, (0., 0.), Some("EPSG:4326")),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn mosaic_read_bands_bbox() {
+        // Two 20 x 10 pixels tiles, overlapping by 5 columns, whose pixels hold
+        // their column on the shared grid, plus one.
+        let tile = |name: &str, first_col: usize| {
+            let x = 500000. + first_col as f64 * 10.;
+            let geo_transform = [x, 10., 0., 5000000., 0., -10.];
+            let (dataset, path) =
+                gtiff_dataset(&format!("mosaic_{name}"), geo_transform, (20, 10), 1);
+            let data = (0..200).map(|index| (first_col + index % 20 + 1) as u16).collect();
+            let mut buffer = gdal::raster::Buffer::new((20, 10), data);
+            dataset.rasterband(1).unwrap().write((0, 0), (20, 10), &mut buffer).unwrap();
+            let band_group = Arc::new(BandGroup::new(&dataset).unwrap());
+            let raster = Raster::<Reference>::new(
+                Bands::from_iter([("B1".to_string(), BandInfo::new(band_group, 1, ()))]),
+                (),
+            );
+            (raster, path)
+        };
+        let (west, west_path) = tile("west", 0);
+        let (east, east_path) = tile("east", 15);
+        let mosaic = Mosaic::new(vec![west, east]).unwrap();
+
+        // Columns 5 to 38 of the top 5 rows, past the east tile's edge.
+        let array = mosaic
+            .read_bands_bbox(&["B1"], (500050., 4999950.), (500380., 5000000.))
+            .unwrap();
+        assert_eq!(array.dim(), (1, 33, 5));
+        for ((_, x, _), &value) in array.indexed_iter() {
+            let col = x + 5;
+            assert_eq!(value, if col < 35 { col as u16 + 1 } else { MOSAIC_NODATA });
+        }
+        assert!(matches!(
+            mosaic.read_bands_bbox(&["B1"], (600000., 4000000.), (600100., 4000100.)),
+            Err(Sentinel2ArrayError::BboxOutsideRaster { .. })
+        ));
+        std::fs::remove_file(west_path).unwrap();
+        std::fs::remove_file(east_path).unwrap();
+    }
+
+    #[rstest]
+    fn display(test_raster: Raster<Sentinel2>) {
+        let display = test_raster.to_string();
+        assert!(display.starts_with(&test_raster.metadata.tile_id().unwrap()));
+        let bands = test_raster
+            .band_names()
+            .into_iter()
+            .map(|band_name| {
+                let resolution = test_raster.band_resolution(band_name).unwrap();
+                format!("{band_name} ({resolution} m)")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        assert!(display.contains(&format!("bands {bands} from ")), "{display}");
+        // Natural order, not the lexicographic B1, B11, B12, B2, ...
+        let spectral = ["B1", "B2", "B3", "B4", "B5", "B6", "B7", "B8", "B8A", "B9", "B11", "B12"];
+        let positions = spectral
+            .iter()
+            .map(|band_name| display.find(&format!(" {band_name} (")).unwrap())
+            .collect::<Vec<_>>();
+        assert!(positions.is_sorted(), "{display}");
+    }
+
+    #[cfg(feature = "async")]
+    #[rstest]
+    #[tokio::test]
+    async fn read_bands_async(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((640, 480), (320, 240));
+        let band_names = vec!["B4", "B11", "B1"];
+        let (first, second) = tokio::join!(
+            test_raster.read_bands_async(band_names.clone(), offset, window),
+            test_raster.read_bands_async(band_names.clone(), (0, 0), window),
+        );
+        let read = |offset| test_raster.read_bands(band_names.clone(), offset, window).unwrap();
+        assert_eq!(first.unwrap(), read(offset));
+        assert_eq!(second.unwrap(), read((0, 0)));
+        assert!(matches!(
+            test_raster.read_bands_async(vec!["B99"], offset, window).await,
+            Err(Sentinel2ArrayError::BandNotFound(_))
+        ));
+    }
+
+    #[rstest]
+    fn read_bands_with_progress(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((640, 480), (64, 48));
+        let calls = std::sync::Mutex::new(Vec::new());
+        let array = test_raster
+            .read_bands_with_progress(["B4", "B11", "B1"], offset, window, |completed, total| {
+                calls.lock().unwrap().push((completed, total))
+            })
+            .unwrap();
+        assert_eq!(array, test_raster.read_bands(["B4", "B11", "B1"], offset, window).unwrap());
+        let mut calls = calls.into_inner().unwrap();
+        calls.sort();
+        assert_eq!(calls, [(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[rstest]
+    fn blocks(test_raster: Raster<Sentinel2>) {
+        // B11 is a 20m band, on a grid of half the 10m one.
+        let (width, height) = test_raster.size().unwrap();
+        let (width, height) = (width / 2, height / 2);
+        let (block_width, block_height) = test_raster.band_block_size("B11").unwrap();
+        let blocks = test_raster.blocks("B11").unwrap().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), width.div_ceil(block_width) * height.div_ceil(block_height));
+        for ((x, y), (window_width, window_height)) in blocks {
+            assert_eq!(x as usize % block_width, 0);
+            assert_eq!(y as usize % block_height, 0);
+            assert_eq!(window_width, block_width.min(width - x as usize));
+            assert_eq!(window_height, block_height.min(height - y as usize));
+        }
+    }
+
+    #[test]
+    fn read_dataset_skips_nameless_bands() {
+        // A band group with a named band and an auxiliary one without a name.
+        let band_group = |name: &str, band_names: &[&str]| {
+            let geo_transform = [5e5, 10., 0., 5e6, 0., -10.];
+            let size = (10, 10);
+            let (dataset, path) =
+                gtiff_dataset(&format!("nameless_{name}"), geo_transform, size, band_names.len());
+            for (index, band_name) in band_names.iter().enumerate() {
+                if !band_name.is_empty() {
+                    let mut raster_band = dataset.rasterband(index + 1).unwrap();
+                    raster_band.set_metadata_item("BANDNAME", band_name, "").unwrap();
+                }
+            }
+            path
+        };
+        let product = |subdataset_paths: &[&Path]| {
+            let mut product = gdal::DriverManager::get_driver_by_name("MEM")
+                .unwrap()
+                .create("", 1, 1, 0)
+                .unwrap();
+            for (index, path) in subdataset_paths.iter().enumerate() {
+                let number = index + 1;
+                let path = path.to_str().unwrap();
+                let key = format!("SUBDATASET_{number}_NAME");
+                product.set_metadata_item(&key, path, "SUBDATASETS").unwrap();
+                let key = format!("SUBDATASET_{number}_DESC");
+                product.set_metadata_item(&key, "band group", "SUBDATASETS").unwrap();
+            }
+            product
+        };
+        let named = band_group("named", &["B4", ""]);
+        let nameless = band_group("nameless", &["", ""]);
+
+        let (bands, _) = Sentinel2::read_dataset(product(&[&named, &nameless])).unwrap();
+        assert_eq!(bands.names(), ["B4"]);
+        assert!(matches!(
+            Sentinel2::read_dataset(product(&[&nameless])),
+            Err(Sentinel2ArrayError::NoBandsFound)
+        ));
+        std::fs::remove_file(named).unwrap();
+        std::fs::remove_file(nameless).unwrap();
+    }
+
+    #[rstest]
+    fn bounds(test_raster: Raster<Sentinel2>) {
+        let [a, _, c, _, e, f] = test_raster.profile().unwrap().transform;
+        let (width, height) = test_raster.size().unwrap();
+        let (min_x, min_y, max_x, max_y) = test_raster.bounds().unwrap();
+        assert_eq!((min_x, max_y), (c, f));
+        assert_eq!((max_x, min_y), (c + a * width as f64, f + e * height as f64));
+
+        // Tiles span about 110 km, i.e. a degree of latitude.
+        let (min_lon, min_lat, max_lon, max_lat) = test_raster.bounds_lonlat().unwrap();
+        assert!((-180. ..180.).contains(&min_lon) && (-180. ..180.).contains(&max_lon));
+        assert!((-90. ..90.).contains(&min_lat) && (-90. ..90.).contains(&max_lat));
+        assert!((0.8..1.2).contains(&(max_lat - min_lat)));
+        assert!(min_lon < max_lon);
+    }
+
+    #[test]
+    fn bands_tie_break() {
+        let band_group = |name: &str| {
+            let geo_transform = [5e5, 10., 0., 5e6, 0., -10.];
+            gtiff_band_group(&format!("tie_break_{name}"), geo_transform, (10, 10), 1)
+        };
+        let (first, first_path) = band_group("a");
+        let (second, second_path) = band_group("b");
+        let kept = |band_groups: [&Arc<BandGroup>; 2]| {
+            let bands = Bands::from_iter(band_groups.map(|band_group| {
+                ("B4".to_string(), BandInfo::new(Arc::clone(band_group), 1, ()))
+            }));
+            bands.get("B4").unwrap().gdal_dataset_path().to_string()
+        };
+        assert_eq!(kept([&first, &second]), first.gdal_dataset_path());
+        assert_eq!(kept([&second, &first]), first.gdal_dataset_path());
+        std::fs::remove_file(first_path).unwrap();
+        std::fs::remove_file(second_path).unwrap();
+    }
+
+    #[rstest]
+    fn read_bands_batch(test_raster: Raster<Sentinel2>) {
+        let band_names = ["B4", "B11", "B1"];
+        let windows = [((0, 0), (32, 32)), ((640, 480), (64, 48)), ((5000, 7000), (17, 9))];
+        let batch = test_raster.read_bands_batch(band_names, &windows).unwrap();
+        assert_eq!(batch.len(), 3);
+        for (array, (offset, window)) in batch.into_iter().zip(windows) {
+            assert_eq!(array, test_raster.read_bands(band_names, offset, window).unwrap());
+        }
+        assert!(matches!(
+            test_raster.read_bands_batch(band_names, &[((0, 0), (1, 1)), ((-1, 0), (1, 1))]),
+            Err(Sentinel2ArrayError::WindowOutOfBounds { .. })
+        ));
+    }
+
+    #[rstest]
+    fn raster_from_with_gdal_options(test_raster: Raster<Sentinel2>) {
+        let raster = Sentinel2::raster_from_with_gdal_options(TEST_DATA, &["ALPHA=NO"]).unwrap();
+        assert_eq!(raster.band_names(), test_raster.band_names());
+        let (offset, window) = ((640, 480), (32, 24));
+        assert_eq!(
+            raster.read_bands(["B4", "B11"], offset, window).unwrap(),
+            test_raster.read_bands(["B4", "B11"], offset, window).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn band_dtype(test_raster: Raster<Sentinel2>) {
+        use gdal::raster::GdalDataType;
+
+        assert_eq!(test_raster.band_dtype("B4").unwrap(), GdalDataType::UInt16);
+        assert!(matches!(
+            test_raster.read_bands_cast::<u8, _>(["B4"], (0, 0), (8, 8)),
+            Err(Sentinel2ArrayError::DataTypeMismatch { .. })
+        ));
+        assert!(test_raster.read_bands_cast::<u32, _>(["B4"], (0, 0), (8, 8)).is_ok());
+    }
+
+    #[test]
+    fn read_dataset_unopenable_subdataset() {
+        // A product listing a subdataset that's gone, as in truncated downloads.
+        let mut product = gdal::DriverManager::get_driver_by_name("MEM")
+            .unwrap()
+            .create("", 1, 1, 0)
+            .unwrap();
+        let missing = std::env::temp_dir().join("rusterio_missing_subdataset.tif");
+        let missing = missing.to_str().unwrap();
+        product.set_metadata_item("SUBDATASET_1_NAME", missing, "SUBDATASETS").unwrap();
+        product.set_metadata_item("SUBDATASET_1_DESC", "band group", "SUBDATASETS").unwrap();
+        assert!(matches!(
+            Sentinel2::read_dataset(product),
+            Err(Sentinel2ArrayError::GdalError(_))
+        ));
+    }
+
+    #[rstest]
+    fn bands(test_raster: Raster<Sentinel2>) {
+        let bands = test_raster.bands().collect::<Vec<_>>();
+        assert!(bands.contains(&("B8", 10)));
+        assert!(bands.contains(&("B11", 20)));
+        let band_names = bands.iter().map(|&(band_name, _)| band_name).collect::<Vec<_>>();
+        assert_eq!(band_names, test_raster.band_names());
+    }
+
+    #[rstest]
+    fn read_no_bands(test_raster: Raster<Sentinel2>) {
+        assert!(matches!(
+            test_raster.read_bands(Vec::<&str>::new(), (0, 0), (1, 1)),
+            Err(Sentinel2ArrayError::NoBandsFound)
+        ));
+        assert!(matches!(
+            test_raster.highest_resolution_transform(&[]),
+            Err(Sentinel2ArrayError::NoBandsFound)
+        ));
+    }
+
+    #[test]
+    fn highest_resolution_transform_tie() {
+        // Two 10 m groups, the second a kilometre east of the first.
+        let band_group = |name: &str, x: f64| {
+            let geo_transform = [x, 10., 0., 5e6, 0., -10.];
+            gtiff_band_group(&format!("transform_tie_{name}"), geo_transform, (10, 10), 1)
+        };
+        let (west, west_path) = band_group("west", 5e5);
+        let (east, east_path) = band_group("east", 5.01e5);
+        let raster = Raster::<Reference>::new(
+            Bands::from_iter([
+                ("W".to_string(), BandInfo::new(Arc::clone(&west), 1, ())),
+                ("E".to_string(), BandInfo::new(east, 1, ())),
+            ]),
+            (),
+        );
+        let transform = raster.highest_resolution_transform(&["E", "W"]).unwrap();
+        assert_eq!(raster.highest_resolution_transform(&["W", "E"]).unwrap(), transform);
+        assert_eq!(transform.m13, 5e5);
+        assert_eq!(raster.grid().unwrap().transform, transform);
+        std::fs::remove_file(west_path).unwrap();
+        std::fs::remove_file(east_path).unwrap();
+    }
+
+    #[rstest]
+    fn epsg(test_raster: Raster<Sentinel2>) {
+        // Tile 33PTM lies in UTM zone 33N.
+        assert_eq!(test_raster.epsg().unwrap(), 32633);
+        let crs = test_raster.crs().unwrap();
+        assert!(crs.is_projected());
+        assert_eq!(crs.linear_units_name().unwrap(), "metre");
+    }
+
+    #[rstest]
+    fn raster_scene_identity(test_raster: Raster<Sentinel2>) {
+        use std::{collections::HashSet, hash::BuildHasher};
+
+        let reopened = Sentinel2::raster_from(TEST_DATA).unwrap();
+        assert_eq!(test_raster, reopened);
+        let hasher = std::collections::hash_map::RandomState::new();
+        assert_eq!(hasher.hash_one(&test_raster), hasher.hash_one(&reopened));
+
+        let scene = |tile_id: &str| {
+            let mut metadata = Metadata::new(format!("{tile_id}.SAFE"));
+            let product_uri =
+                format!("S2B_MSIL2A_20241126T093239_N0511_R136_{tile_id}_20241126T120342");
+            metadata.insert("PRODUCT_URI".into(), product_uri);
+            metadata.insert("PRODUCT_START_TIME".into(), "2024-11-26T09:32:39.024Z".into());
+            metadata.insert("PROCESSING_BASELINE".into(), "05.11".into());
+            Raster::<Sentinel2>::new(Bands::default(), metadata.into())
+        };
+        assert_ne!(scene("T33PTM"), scene("T33PTN"));
+        let other_tile = scene("T33PTN");
+        assert_ne!(test_raster, other_tile);
+
+        // The datasets band groups cache don't take part in hashing.
+        #[allow(clippy::mutable_key_type)]
+        let scenes = HashSet::from([test_raster, reopened, other_tile]);
+        assert_eq!(scenes.len(), 2);
+    }
+
+    #[rstest]
+    fn resolutions(test_raster: Raster<Sentinel2>) {
+        assert_eq!(test_raster.size().unwrap(), (10980, 10980));
+        assert_eq!(test_raster.resolutions(), [10, 20, 60]);
+        assert_eq!(test_raster.bands_at(10), ["B2", "B3", "B4", "B8"]);
+        assert!(test_raster.bands_at(20).contains(&"SCL"));
+        assert!(test_raster.bands_at(30).is_empty());
+    }
+
+    #[test]
+    fn natural_band_order() {
+        let geo_transform = [300000., 10., 0., 1600000., 0., -10.];
+        let (band_group, path) = gtiff_band_group("natural_band_order", geo_transform, (1, 1), 1);
+        let band_names = ["SCL", "B12", "B8A", "B2", "WVP", "B10", "B8", "B1", "AOT", "B9"];
+        let bands = Bands::from_iter(band_names.map(|band_name| {
+            (band_name.to_string(), BandInfo::new(Arc::clone(&band_group), 1, ()))
+        }));
+        assert_eq!(
+            bands.names(),
+            ["B1", "B2", "B8", "B8A", "B9", "B10", "B12", "AOT", "SCL", "WVP"]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[rstest]
+    fn read_all_bands(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((50, 60), (8, 6));
+        let (band_names, array) = test_raster.read_all_bands(offset, window).unwrap();
+        assert_eq!(band_names.len(), array.dim().0);
+        assert_eq!(band_names.iter().collect::<Vec<_>>(), test_raster.band_names());
+        let b8 = band_names.iter().position(|name| name == "B8").unwrap();
+        assert_eq!(
+            array.index_axis(Axis(0), b8),
+            test_raster.read_band("B8", offset, window).unwrap()
+        );
+    }
+
+    #[cfg(feature = "polars")]
+    #[rstest]
+    fn read_bands_dataframe(test_raster: Raster<Sentinel2>) {
+        let (offset, window) = ((700, 900), (20, 10));
+        let dataframe = test_raster
+            .read_bands_dataframe(vec!["B4", "B11"], offset, window)
+            .unwrap();
+        assert_eq!(dataframe.shape(), (20 * 10, 2 + 4));
+        assert_eq!(dataframe.get_column_names(), ["x", "y", "lon", "lat", "B4", "B11"]);
+        let lon = dataframe.column("lon").unwrap().f64().unwrap().get(0).unwrap();
+        assert!((12. ..18.).contains(&lon));
+    }
+
     /* #[rstest]
     fn it_works(test_raster: Raster) {
         print!(
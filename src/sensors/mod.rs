@@ -2,8 +2,11 @@
 
 use std::fmt::Debug;
 
-mod sentinel2;
-pub use sentinel2::Sentinel2;
+pub(crate) mod sentinel2;
+pub use sentinel2::{
+    Acquisition, ClassResample, ProductLevel, Radiance, RasterOptions, RasterSummary,
+    Reflectance, S2Band, Sentinel2, Stretch,
+};
 
 pub trait Sensor {
     type RasterMetadata: Debug + Send + Sync;
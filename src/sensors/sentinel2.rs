@@ -1,11 +1,29 @@
+use chrono::{DateTime, Utc};
 use gdal::Metadata as GdalMetadata;
 use itertools::Itertools;
-use std::sync::Arc;
+use log::warn;
+use rasters::{
+    prelude::{PixelTransform, RasterPathReader},
+    reader::ChunkReader,
+};
+use nalgebra::Point2;
+use ndarray::{Array2, Array3, ArrayView2, Axis, Zip};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use crate::{
     components::{
-        band::{BandGroup, BandInfo, Bands},
-        metadata::Metadata,
+        band::{gdal_calibration, BandGroup, BandInfo, BandStats, Bands},
+        converter::PixelConverter,
+        metadata::{Metadata, MetadataDelta},
+        raster::{window_transform, BandMismatch, Grid, Raster, BAND_ALIGNMENT_TOLERANCE},
+        read_builder::{ReadBuilder, ReadResult},
         reader::DatasetReader,
     },
     errors::{Result, Sentinel2ArrayError},
@@ -13,6 +31,15 @@ use crate::{
 
 use super::Sensor;
 
+mod angles;
+mod product_files;
+#[cfg(feature = "serde")]
+mod scene_index;
+
+/// Farthest a band's central wavelength may be from the one asked for in
+/// `band_for_wavelength`, in nanometers.
+const WAVELENGTH_TOLERANCE_NM: f64 = 50.;
+
 #[derive(Debug)]
 pub struct Sentinel2;
 
@@ -24,72 +51,291 @@ impl Sensor for Sentinel2 {
 }
 
 impl DatasetReader for Sentinel2 {
+    /// The driver opens a `.SAFE` directory through its `MTD_MSIL2A.xml`.
+    fn dataset_path(path: &Path) -> Result<PathBuf> {
+        if path.is_dir() {
+            product_files::metadata_file(path).ok_or_else(|| {
+                Sentinel2ArrayError::ProductMetadataNotFound(path.display().to_string())
+            })
+        } else {
+            Ok(path.to_path_buf())
+        }
+    }
+
     fn read_dataset(dataset: gdal::Dataset) -> Result<(Bands<BandMetadata>, RasterMetadata)> {
-        let (metadata, bandgroup_datasets) = Self::parse_raster_metadata(&dataset)?;
-        let bands = bandgroup_datasets
+        Self::read_dataset_with_options(dataset, &RasterOptions::default(), &[])
+    }
+}
+
+impl Sentinel2 {
+    /// Like `raster_from`, but reading the subdatasets `options` include.
+    pub fn raster_from_with_options<P: AsRef<Path>>(
+        path: P,
+        options: &RasterOptions,
+    ) -> Result<Raster<Self>> {
+        let dataset = Self::open_dataset(path)?;
+        let band_mismatch = if options.reproject_mismatched_crs {
+            BandMismatch::Warp
+        } else {
+            BandMismatch::Reject
+        };
+        Self::read_dataset_with_options(dataset, options, &[])
+            .map(|(bands, metadata)| {
+                Raster::new(bands, metadata).with_band_mismatch(band_mismatch)
+            })
+    }
+
+    /// Like `raster_from`, but opening the product, its subdatasets and the
+    /// datasets reads reopen with GDAL open options like `KEY=VALUE`, e.g.
+    /// `ALPHA=YES` for the driver to add an alpha band.
+    pub fn raster_from_with_gdal_options<P: AsRef<Path>>(
+        path: P,
+        open_options: &[&str],
+    ) -> Result<Raster<Self>> {
+        let dataset = Self::open_dataset_with_options(path, open_options)?;
+        Self::read_dataset_with_options(dataset, &RasterOptions::default(), open_options)
+            .map(|(bands, metadata)| Raster::new(bands, metadata))
+    }
+
+    fn read_dataset_with_options(
+        dataset: gdal::Dataset,
+        options: &RasterOptions,
+        open_options: &[&str],
+    ) -> Result<(Bands<BandMetadata>, RasterMetadata)> {
+        let (metadata, subdatasets) = Self::parse_raster_metadata(&dataset)?;
+        let bands = subdatasets
             .iter()
-            .map(Self::read_bandgroup_dataset)
+            .filter(|subdataset| options.includes(subdataset))
+            .map(|subdataset| {
+                let dataset_options = gdal::DatasetOptions {
+                    open_options: Some(open_options),
+                    ..Default::default()
+                };
+                let bandgroup_dataset = gdal::Dataset::open_ex(&subdataset.name, dataset_options)?;
+                Self::read_bandgroup_dataset(&bandgroup_dataset, open_options)
+            })
             .process_results(|iter| Bands::from_iter(iter.flatten()))?;
+        if bands.is_empty() {
+            return Err(Sentinel2ArrayError::NoBandsFound);
+        }
+        if !options.reproject_mismatched_crs && !bands.share_projection()? {
+            return Err(Sentinel2ArrayError::MultipleProjectionsInDataset(
+                dataset.description()?,
+            ));
+        }
         Ok((bands, metadata))
     }
-}
 
-impl Sentinel2 {
+    /// Read only the product level metadata and subdataset listing of the
+    /// product at `path`, without opening its band groups.
+    pub fn probe<P: AsRef<Path>>(path: P) -> Result<ProductProbe> {
+        let dataset = Self::open_dataset(path)?;
+        Self::parse_raster_metadata(&dataset)
+            .map(|(metadata, subdatasets)| ProductProbe { metadata, subdatasets })
+    }
+
+    /// Like `raster_from`, but fails with `ValidationFailed` listing all the
+    /// problems [`Raster::validate`] finds with the product.
+    pub fn raster_from_strict<P: AsRef<Path>>(path: P) -> Result<Raster<Self>> {
+        let raster = Self::raster_from(path)?;
+        raster.validate()?;
+        Ok(raster)
+    }
+
+    /// Open the product at `path` naming its bands after `band_map` instead of
+    /// their `BANDNAME` metadata. It maps band names to the GDAL name of the
+    /// subdataset holding them, as listed by [`Sentinel2::probe`], and their
+    /// (1 based) band index in it.
+    pub fn raster_from_band_map<P: AsRef<Path>>(
+        path: P,
+        band_map: HashMap<String, (String, usize)>,
+    ) -> Result<Raster<Self>> {
+        let dataset = Self::open_dataset(path)?;
+        let (metadata, _) = Self::parse_raster_metadata(&dataset)?;
+        let mut band_groups = HashMap::new();
+        let bands = band_map
+            .into_iter()
+            .map(|(band_name, (subdataset_name, index))| {
+                let (band_group, bandgroup_dataset) = match band_groups.entry(subdataset_name) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let bandgroup_dataset = gdal::Dataset::open(entry.key())?;
+                        let band_group = Arc::new(BandGroup::new(&bandgroup_dataset)?);
+                        entry.insert((band_group, bandgroup_dataset))
+                    }
+                };
+                let raster_band = bandgroup_dataset.rasterband(index)?;
+                let calibration = gdal_calibration(&raster_band);
+                let (_, metadata) = Self::parse_rasterband_metadata(raster_band)?;
+                let band_info = BandInfo::new(Arc::clone(band_group), index, metadata);
+                Ok((band_name, band_info.with_calibration(calibration)))
+            })
+            .collect::<Result<Bands<BandMetadata>>>()?;
+        Ok(Raster::new(bands, metadata))
+    }
+
     fn parse_raster_metadata(
         raster_dataset: &gdal::Dataset,
-    ) -> Result<(RasterMetadata, Vec<gdal::Dataset>)> {
+    ) -> Result<(RasterMetadata, Vec<Subdataset>)> {
         let mut raster_metadata = RasterMetadata::new(raster_dataset.description()?);
-        let mut subdatasets = Vec::new();
+        let mut names = Vec::new();
+        let mut descriptions = Vec::new();
         for gdal::MetadataEntry { domain, key, value } in raster_dataset.metadata() {
             match domain.as_str() {
                 "" => raster_metadata.0.insert(key, value),
-                "SUBDATASETS" if key.contains("NAME") => {
-                    subdatasets.push(gdal::Dataset::open(value)?)
-                }
+                "SUBDATASETS" if key.ends_with("_NAME") => names.push(value),
+                "SUBDATASETS" if key.ends_with("_DESC") => descriptions.push(value),
                 _ => (),
             };
         }
+        let subdatasets = names
+            .into_iter()
+            .zip(descriptions)
+            .map(|(name, description)| Subdataset { name, description })
+            .collect();
         Ok((raster_metadata, subdatasets))
     }
 
-    fn read_bandgroup_dataset<'a>(
-        bandgroup_dataset: &'a gdal::Dataset,
+    /// Named bands of `bandgroup_dataset`. Bands without a name, like those of
+    /// some auxiliary subdatasets, get skipped with a warning.
+    fn read_bandgroup_dataset(
+        bandgroup_dataset: &gdal::Dataset,
+        open_options: &[&str],
     ) -> Result<Vec<(String, BandInfo<BandMetadata>)>> {
-        let band_group = Arc::new(BandGroup::new(&bandgroup_dataset)?);
-        bandgroup_dataset
-            .rasterbands()
-            .enumerate()
-            .map(|(index, raster_band)| {
-                let (band_name, metadata) = Self::parse_rasterband_metadata(raster_band?)?;
-                Ok((
-                    band_name,
-                    BandInfo::new(Arc::clone(&band_group), index + 1, metadata),
-                ))
-            })
-            .collect()
+        let band_group =
+            Arc::new(BandGroup::new(bandgroup_dataset)?.with_open_options(open_options));
+        let mut bands = Vec::new();
+        for (index, raster_band) in bandgroup_dataset.rasterbands().enumerate() {
+            let raster_band = raster_band?;
+            let calibration = gdal_calibration(&raster_band);
+            let (band_name, metadata) = Self::parse_rasterband_metadata(raster_band)?;
+            if band_name.is_empty() {
+                warn!(
+                    "Skipping band {} of {}, which has no BANDNAME nor description.",
+                    index + 1,
+                    bandgroup_dataset.description()?
+                );
+                continue;
+            }
+            bands.push((
+                band_name,
+                BandInfo::new(Arc::clone(&band_group), index + 1, metadata)
+                    .with_calibration(calibration),
+            ));
+        }
+        Ok(bands)
     }
 
-    fn parse_rasterband_metadata(
+    /// Name and metadata of `raster_band`, named by its `BANDNAME` item or
+    /// else by the start of its description.
+    pub(crate) fn parse_rasterband_metadata(
         raster_band: gdal::raster::RasterBand,
     ) -> Result<(String, BandMetadata)> {
         let mut band_name = String::new();
         let mut  metadata = BandMetadata::new(raster_band.description()?);
         for gdal::MetadataEntry { domain, key, value } in raster_band.metadata() {
-            if matches!(domain.as_str(), "") {
+            match (domain.as_str(), key.as_str()) {
                 // Should only exist one.
-                if matches!(key.as_str(), "BANDNAME") {
-                    band_name.push_str(&value);
-                } else {
-                    metadata.0.insert(key, value);
-                }
+                ("", "BANDNAME") => band_name.push_str(&value),
+                ("", _) | ("IMAGE_STRUCTURE", "NBITS") => metadata.0.insert(key, value),
+                _ => (),
             }
         }
+        if band_name.is_empty() {
+            // Descriptions read like `B4, central wavelength 665 nm`.
+            let description = metadata.0.description();
+            band_name.push_str(description.split(',').next().unwrap_or_default().trim());
+        }
         Ok((band_name, metadata))
     }
 }
 
 #[derive(Debug)]
+pub struct Subdataset {
+    pub name: String,
+    pub description: String,
+}
+
+impl Subdataset {
+    /// Whether this is the 8 bit true colour image, named like
+    /// `SENTINEL2_L2A:<path>:TCI:EPSG_32633`.
+    pub fn is_true_colour_image(&self) -> bool {
+        self.name.contains(":TCI:")
+    }
+}
+
+/// What [`Sentinel2::raster_from_with_options`] reads of a product.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RasterOptions {
+    /// Read the bands of the true colour image subdataset too.
+    pub include_tci: bool,
+    /// Accept bands in another projection than the finest band's, warping
+    /// them bilinearly onto the read grid, instead of failing with
+    /// `MultipleProjectionsInDataset`.
+    pub reproject_mismatched_crs: bool,
+}
+
+impl RasterOptions {
+    /// Whether the bands of `subdataset` get read.
+    pub fn includes(&self, subdataset: &Subdataset) -> bool {
+        self.include_tci || !subdataset.is_true_colour_image()
+    }
+}
+
+/// Product level view of a Sentinel-2 product, see [`Sentinel2::probe`].
+#[derive(Debug)]
+pub struct ProductProbe {
+    pub metadata: RasterMetadata,
+    pub subdatasets: Vec<Subdataset>,
+}
+
+impl ProductProbe {
+    /// Sorted band names listed in the subdataset descriptions, which read
+    /// like `Bands B2, B3, B4, B8 with 10m resolution, UTM 33N`.
+    pub fn band_names(&self) -> Vec<&str> {
+        self.subdatasets
+            .iter()
+            .filter_map(|subdataset| {
+                subdataset
+                    .description
+                    .strip_prefix("Bands ")?
+                    .split_once(" with ")
+                    .map(|(band_names, _)| band_names.split(", "))
+            })
+            .flatten()
+            .sorted()
+            .dedup()
+            .collect()
+    }
+}
+
+/// Description of a [`Raster<Sentinel2>`] without its GDAL handles, see
+/// [`Raster::summary`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RasterSummary {
+    /// Path of the product.
+    pub path: String,
+    /// WKT projection of the highest resolution grid.
+    pub proj: String,
+    /// Product level metadata items.
+    pub metadata: BTreeMap<String, String>,
+    /// Sorted band names with their pixel size in meters.
+    pub bands: Vec<(String, f64)>,
+    /// Affine coefficients `(a, b, c, d, e, f)` of the highest resolution grid,
+    /// as in the transform of [`Raster::profile`].
+    pub transform: [f64; 6],
+}
+
+#[derive(Debug, Clone)]
 pub struct RasterMetadata(Metadata);
+
+impl From<Metadata> for RasterMetadata {
+    fn from(metadata: Metadata) -> Self {
+        Self(metadata)
+    }
+}
+
 impl RasterMetadata {
     pub fn new(description: String) -> Self {
         Self(Metadata::new(description))
@@ -104,6 +350,1173 @@ impl RasterMetadata {
             })
             .map_err(Sentinel2ArrayError::GdalError)
     }
+
+    /// Processing level of the product, from its `PRODUCT_TYPE`, e.g.
+    /// `S2MSI2A`.
+    pub fn product_level(&self) -> Result<ProductLevel> {
+        self.0.get_with("PRODUCT_TYPE", |product_type| {
+            if product_type.ends_with("1C") {
+                Some(ProductLevel::L1C)
+            } else if product_type.ends_with("2A") {
+                Some(ProductLevel::L2A)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Fail with `NotLevel2A` about `what` if the product is of level-1C.
+    /// Products whose level isn't known are given the benefit of the doubt.
+    fn require_level_2a(&self, what: &str) -> Result<()> {
+        match self.product_level() {
+            Ok(ProductLevel::L1C) => Err(Sentinel2ArrayError::NotLevel2A(what.into())),
+            _ => Ok(()),
+        }
+    }
+
+    /// MGRS tile of the product, e.g. `T33PTM`, taken from its `PRODUCT_URI`.
+    pub fn tile_id(&self) -> Result<String> {
+        self.0.get_with("PRODUCT_URI", |product_uri| {
+            product_uri
+                .split('_')
+                .nth(5)
+                .filter(|tile_id| tile_id.len() == 6 && tile_id.starts_with('T'))
+                .map(String::from)
+        })
+    }
+
+    /// Percentage of the tile assessed as cloudy, from its
+    /// `CLOUD_COVERAGE_ASSESSMENT` quality indicator.
+    pub fn cloud_coverage(&self) -> Result<f32> {
+        self.0.get_parsed("CLOUD_COVERAGE_ASSESSMENT")
+    }
+
+    /// Percentage of the tile with no data, e.g. outside of the swath, from
+    /// its `NODATA_PIXEL_PERCENTAGE` quality indicator.
+    pub fn nodata_coverage(&self) -> Result<f32> {
+        self.0.get_parsed("NODATA_PIXEL_PERCENTAGE")
+    }
+
+    pub fn product_start_time(&self) -> Result<&String> {
+        self.0.get("PRODUCT_START_TIME")
+    }
+
+    pub fn boa_quantification_value(&self) -> Result<f32> {
+        self.require_level_2a("BOA_QUANTIFICATION_VALUE")?;
+        self.0.get_parsed("BOA_QUANTIFICATION_VALUE")
+    }
+
+    /// Offset added to digital numbers before dividing them by the
+    /// quantification value, 0 for products of baselines before 04.00 which
+    /// don't have one.
+    pub fn boa_add_offset(&self) -> Result<f32> {
+        match self.0.get("BOA_ADD_OFFSET") {
+            Ok(_) => self.0.get_parsed("BOA_ADD_OFFSET"),
+            Err(_) => Ok(0.),
+        }
+    }
+
+    /// Band specific add offsets of the `BOA_ADD_OFFSET_<band>` items, which
+    /// products of baselines 04.00 and later carry. Bands are named either
+    /// directly or by their `band_id` index in the product metadata.
+    pub fn boa_offsets(&self) -> Result<HashMap<String, f32>> {
+        self.0
+            .iter()
+            .filter_map(|(key, _)| {
+                let band = key.strip_prefix("BOA_ADD_OFFSET_")?;
+                let band_name = match band.parse::<usize>() {
+                    Ok(band_id) => angles::BAND_IDS.get(band_id).copied().unwrap_or(band),
+                    Err(_) => band,
+                };
+                Some(self.0.get_parsed(key).map(|offset| (band_name.to_string(), offset)))
+            })
+            .collect()
+    }
+
+    /// Digital number ESA marks pixels without data with, whatever the
+    /// band-level no data value.
+    pub fn special_value_nodata(&self) -> Result<u16> {
+        self.0.get_parsed("SPECIAL_VALUE_NODATA")
+    }
+
+    /// Earth-sun distance correction factor `U` of the acquisition date.
+    pub fn reflectance_conversion_u(&self) -> Result<f32> {
+        self.0.get_parsed("REFLECTANCE_CONVERSION_U")
+    }
+
+    /// Mean sun zenith and azimuth angles over the tile, in degrees.
+    pub fn mean_sun_angles(&self) -> Result<(f64, f64)> {
+        Ok((
+            self.0.get_parsed("MEAN_SUN_ZENITH_ANGLE")?,
+            self.0.get_parsed("MEAN_SUN_AZIMUTH_ANGLE")?,
+        ))
+    }
+
+    /// Mean sun and viewing angles over the tile.
+    pub fn scene_geometry(&self) -> Result<SceneGeometry> {
+        let (sun_zenith, sun_azimuth) = self.mean_sun_angles()?;
+        Ok(SceneGeometry {
+            sun_zenith,
+            sun_azimuth,
+            view_zenith: self.0.get_parsed("MEAN_INCIDENCE_ZENITH_ANGLE")?,
+            view_azimuth: self.0.get_parsed("MEAN_INCIDENCE_AZIMUTH_ANGLE")?,
+        })
+    }
+
+    /// Ancillary atmospheric scalars the Level-2A processor used. Only L2A
+    /// products have them, L1C ones fail with `NotLevel2A`.
+    pub fn atmospheric_parameters(&self) -> Result<AtmosphericParams> {
+        self.require_level_2a("atmospheric parameters")?;
+        Ok(AtmosphericParams {
+            ozone: self.0.get_parsed("OZONE_VALUE")?,
+            ozone_source: self.0.get("OZONE_SOURCE")?.clone(),
+            water_vapour: self.0.get_parsed("GRANULE_MEAN_WV").ok(),
+            aerosol_optical_thickness: self.0.get_parsed("GRANULE_MEAN_AOT").ok(),
+        })
+    }
+}
+
+/// How the digital numbers of a band get mapped onto 8 bits for display, see
+/// [`Raster::to_rgb_image`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Stretch {
+    /// From the band's lowest to its highest value.
+    #[default]
+    MinMax,
+    /// From the `low` to the `high` percentile of the band's values, in
+    /// percent, e.g. `Percentile(2., 98.)`.
+    Percentile(f32, f32),
+}
+
+impl Stretch {
+    /// Values of `band` mapped to 0 and 255.
+    pub fn bounds(self, band: ArrayView2<u16>) -> (f32, f32) {
+        let mut values = band.iter().copied().collect::<Vec<_>>();
+        if values.is_empty() {
+            return (0., 0.);
+        }
+        values.sort_unstable();
+        let percentile = |percent: f32| {
+            let rank = (percent.clamp(0., 100.) / 100. * (values.len() - 1) as f32).round();
+            values[rank as usize] as f32
+        };
+        match self {
+            Self::MinMax => (percentile(0.), percentile(100.)),
+            Self::Percentile(low, high) => (percentile(low), percentile(high)),
+        }
+    }
+}
+
+/// Processing level of a Sentinel-2 product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductLevel {
+    /// Top of atmosphere reflectance.
+    L1C,
+    /// Bottom of atmosphere reflectance, with the scene classification and
+    /// atmospheric bands.
+    L2A,
+}
+
+/// Ancillary atmospheric scalars of a Level-2A tile. Surface pressure isn't
+/// part of the product metadata, only of its auxiliary files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtmosphericParams {
+    /// Total ozone column, in Dobson units.
+    pub ozone: f64,
+    /// Auxiliary data the ozone comes from, e.g. `AUX_ECMWFT`.
+    pub ozone_source: String,
+    /// Mean water vapour column over the tile, in g/cm², from processing
+    /// baseline 04.00 on.
+    pub water_vapour: Option<f64>,
+    /// Mean aerosol optical thickness at 550 nm over the tile, from
+    /// processing baseline 04.00 on.
+    pub aerosol_optical_thickness: Option<f64>,
+}
+
+/// When, where and how a product was acquired and processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Acquisition {
+    /// Start of the sensing of the product's datatake.
+    pub sensing_time: DateTime<Utc>,
+    /// MGRS tile, e.g. `T33PTM`.
+    pub tile_id: String,
+    pub relative_orbit: u16,
+    /// e.g. `05.11`.
+    pub processing_baseline: String,
+}
+
+/// Mean acquisition geometry of a tile, angles in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneGeometry {
+    pub sun_zenith: f64,
+    pub sun_azimuth: f64,
+    pub view_zenith: f64,
+    pub view_azimuth: f64,
+}
+
+impl Raster<Sentinel2> {
+    /// Processing level of the product, see [`RasterMetadata::product_level`].
+    pub fn product_level(&self) -> Result<ProductLevel> {
+        self.metadata.product_level()
+    }
+
+    /// Percentage of cloudy pixels of the product, see
+    /// [`RasterMetadata::cloud_coverage`].
+    pub fn cloud_coverage(&self) -> Result<f32> {
+        self.metadata.cloud_coverage()
+    }
+
+    /// Percentage of no data pixels of the product, see
+    /// [`RasterMetadata::nodata_coverage`].
+    pub fn nodata_coverage(&self) -> Result<f32> {
+        self.metadata.nodata_coverage()
+    }
+
+    /// Sensing time, tile, relative orbit and processing baseline of the
+    /// product, parsed from its metadata.
+    pub fn acquisition(&self) -> Result<Acquisition> {
+        let metadata = &self.metadata.0;
+        Ok(Acquisition {
+            sensing_time: metadata.get_with("PRODUCT_START_TIME", |start_time| {
+                DateTime::parse_from_rfc3339(start_time.trim())
+                    .ok()
+                    .map(|sensing_time| sensing_time.to_utc())
+            })?,
+            tile_id: self.metadata.tile_id()?,
+            relative_orbit: metadata.get_parsed("SENSING_ORBIT_NUMBER")?,
+            processing_baseline: metadata.get("PROCESSING_BASELINE")?.trim().to_string(),
+        })
+    }
+
+    /// Product metadata items that differ between this raster and `other`.
+    pub fn metadata_diff(&self, other: &Raster<Sentinel2>) -> Vec<MetadataDelta> {
+        self.metadata.0.diff(&other.metadata.0)
+    }
+
+    /// Check the raster is a complete and well formed product: all spectral
+    /// bands of its processing level are present, at one of the standard
+    /// resolutions and with invertible transforms, and its quantification
+    /// value is known.
+    pub fn validate(&self) -> Result<()> {
+        const SPECTRAL_BANDS: [&str; 13] = [
+            "B1", "B2", "B3", "B4", "B5", "B6", "B7", "B8", "B8A", "B9", "B10", "B11", "B12",
+        ];
+        let mut issues = Vec::new();
+        let (level, quantification_key) = match self.metadata.0.get("PRODUCT_TYPE") {
+            Ok(product_type) if product_type.ends_with("1C") => ("L1C", "QUANTIFICATION_VALUE"),
+            Ok(product_type) if product_type.ends_with("2A") => ("L2A", "BOA_QUANTIFICATION_VALUE"),
+            Ok(product_type) => {
+                issues.push(format!("unknown product type {product_type}"));
+                ("", "")
+            }
+            Err(error) => {
+                issues.push(error.to_string());
+                ("", "")
+            }
+        };
+        if !level.is_empty() {
+            // Cirrus doesn't make it into the surface reflectance of L2A.
+            for band_name in SPECTRAL_BANDS
+                .into_iter()
+                .filter(|&band_name| !(level == "L2A" && band_name == "B10"))
+            {
+                if self.band_metadata(band_name).is_err() {
+                    issues.push(format!("missing {level} band {band_name}"));
+                }
+            }
+            if let Err(error) = self.metadata.0.get_parsed::<f32>(quantification_key) {
+                issues.push(error.to_string());
+            }
+        }
+        for band_name in self.band_names() {
+            let geo_transform = self.band_geo_transform(band_name)?;
+            if ![10., 20., 60.]
+                .iter()
+                .any(|resolution| (geo_transform[1] - resolution).abs() <= BAND_ALIGNMENT_TOLERANCE)
+            {
+                issues.push(format!(
+                    "band {band_name} has non standard resolution {}",
+                    geo_transform[1]
+                ));
+            }
+            if geo_transform[1] * geo_transform[5] - geo_transform[2] * geo_transform[4] == 0. {
+                issues.push(format!("band {band_name} has a non invertible transform"));
+            }
+            match self.band_metadata(band_name)?.spatial_resolution() {
+                Ok(Some(resolution)) if resolution != self.band_resolution_precise(band_name)? => {
+                    issues.push(format!(
+                        "band {band_name} has resolution {} but metadata says {resolution}",
+                        geo_transform[1]
+                    ));
+                }
+                Ok(_) => {}
+                Err(error) => issues.push(error.to_string()),
+            }
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Sentinel2ArrayError::ValidationFailed(issues))
+        }
+    }
+
+    /// Like `read_bands`, with the bands named by [`S2Band`]s.
+    pub fn read_bands_typed(
+        &self,
+        bands: &[S2Band],
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<u16>> {
+        self.read_bands(bands.iter().map(|band| band.name()), offset, window)
+    }
+
+    /// Band whose central wavelength, as its `WAVELENGTH` item or else the
+    /// S2A/S2B average of [`S2Band::central_wavelength`] tells, is the
+    /// nearest to `wavelength` nanometers, the first in natural order on
+    /// ties. Fails with `NoBandNearWavelength` if none is within
+    /// `WAVELENGTH_TOLERANCE_NM` of it.
+    pub fn band_for_wavelength(&self, wavelength: f64) -> Result<&str> {
+        let mut nearest: Option<(&str, f64)> = None;
+        for band_name in self.band_names() {
+            let central_wavelength = match self.band_metadata(band_name)?.wavelength()? {
+                Some(central_wavelength) => central_wavelength,
+                None => match S2Band::from_name(band_name).and_then(S2Band::central_wavelength) {
+                    Some(central_wavelength) => central_wavelength,
+                    None => continue,
+                },
+            };
+            let distance = (central_wavelength - wavelength).abs();
+            if nearest.is_none_or(|(_, nearest_distance)| distance < nearest_distance) {
+                nearest = Some((band_name, distance));
+            }
+        }
+        nearest
+            .filter(|&(_, distance)| distance <= WAVELENGTH_TOLERANCE_NM)
+            .map(|(band_name, _)| band_name)
+            .ok_or(Sentinel2ArrayError::NoBandNearWavelength {
+                wavelength,
+                tolerance: WAVELENGTH_TOLERANCE_NM,
+            })
+    }
+
+    /// Like `read_bands`, with the bands nearest to `wavelengths` nanometers,
+    /// see `band_for_wavelength`.
+    pub fn read_bands_by_wavelength(
+        &self,
+        wavelengths: &[f64],
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<u16>> {
+        let band_names = wavelengths
+            .iter()
+            .map(|&wavelength| self.band_for_wavelength(wavelength))
+            .collect::<Result<Vec<_>>>()?;
+        self.read_bands(band_names, offset, window)
+    }
+
+    /// Read bands as surface reflectance, see [`Reflectance`] for how digital
+    /// numbers get converted.
+    pub fn read_bands_reflectance<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<f32>> {
+        self.read_bands_convert(band_names, offset, window, &Reflectance::from_raster(self)?)
+    }
+
+    /// Combine the surface reflectance of `band_a` and `band_b`, resampled onto
+    /// their highest resolution grid, pixel by pixel with `combine`, e.g.
+    /// `|a, b| (a - b) / (a + b)` for a normalized difference.
+    pub fn read_bands_combine<F>(
+        &self,
+        band_a: &str,
+        band_b: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+        combine: F,
+    ) -> Result<Array2<f32>>
+    where
+        F: Fn(f32, f32) -> f32 + Sync,
+    {
+        let reflectance = self.read_bands_reflectance([band_a, band_b], offset, window)?;
+        Ok(Zip::from(reflectance.index_axis(Axis(0), 0))
+            .and(reflectance.index_axis(Axis(0), 1))
+            .par_map_collect(|&a, &b| combine(a, b)))
+    }
+
+    /// Normalized difference `(a - b) / (a + b)` of the reflectances of
+    /// `band_a` and `band_b`, `NaN` where their sum is 0.
+    pub fn normalized_difference(
+        &self,
+        band_a: &str,
+        band_b: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array2<f32>> {
+        self.normalized_difference_with_fill(band_a, band_b, offset, window, f32::NAN)
+    }
+
+    /// Like `normalized_difference`, with `fill` where the sum is 0.
+    pub fn normalized_difference_with_fill(
+        &self,
+        band_a: &str,
+        band_b: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+        fill: f32,
+    ) -> Result<Array2<f32>> {
+        self.read_bands_combine(band_a, band_b, offset, window, |a, b| {
+            if a + b == 0. {
+                fill
+            } else {
+                (a - b) / (a + b)
+            }
+        })
+    }
+
+    /// Normalized difference vegetation index, of `B8` and `B4`.
+    pub fn ndvi(&self, offset: (isize, isize), window: (usize, usize)) -> Result<Array2<f32>> {
+        self.normalized_difference("B8", "B4", offset, window)
+    }
+
+    /// Normalized difference water index (McFeeters), of `B3` and `B8`.
+    pub fn ndwi(&self, offset: (isize, isize), window: (usize, usize)) -> Result<Array2<f32>> {
+        self.normalized_difference("B3", "B8", offset, window)
+    }
+
+    /// Serializable description of the raster, see [`RasterSummary`].
+    pub fn summary(&self) -> Result<RasterSummary> {
+        let profile = self.profile()?;
+        let bands = self
+            .band_names()
+            .into_iter()
+            .map(|band_name| {
+                self.band_resolution_precise(band_name)
+                    .map(|resolution| (band_name.clone(), resolution))
+            })
+            .collect::<Result<_>>()?;
+        Ok(RasterSummary {
+            path: self.metadata.0.description().into(),
+            proj: profile.crs,
+            metadata: self
+                .metadata
+                .0
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            bands,
+            transform: profile.transform,
+        })
+    }
+
+    /// Product-level no data digital number, see
+    /// [`RasterMetadata::special_value_nodata`].
+    pub fn product_nodata(&self) -> Result<u16> {
+        self.metadata.special_value_nodata()
+    }
+
+    /// Like `read_bands_reflectance`, but with no data pixels set to `NaN`. A
+    /// band's own no data value takes precedence over the product's.
+    pub fn read_bands_reflectance_masked<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<f32>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let reflectance = Reflectance::from_raster(self)?;
+        let no_data_values = band_names
+            .iter()
+            .map(|&band_name| {
+                let no_data_value = match self.band_no_data_value(band_name)? {
+                    Some(no_data_value) => no_data_value as u16,
+                    None => self.product_nodata()?,
+                };
+                Ok((band_name, no_data_value))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        self.read_bands_as(band_names, offset, window, |band_name, value| {
+            if no_data_values.get(band_name) == Some(&value) {
+                f32::NAN
+            } else {
+                reflectance.convert(band_name, value)
+            }
+        })
+    }
+
+    /// Like `read_bands_reflectance`, but in `f64` for computations that
+    /// accumulate many terms. Takes twice the memory of the `f32` read.
+    pub fn read_bands_f64<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array3<f64>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let reflectance = Reflectance::from_raster(self)?;
+        self.read_bands_as(band_names, offset, window, |band_name, value| {
+            reflectance.convert_f64(band_name, value)
+        })
+    }
+
+    /// Read bands as surface reflectance into a `(band, height, width)` tensor,
+    /// with bands in requested order. The tensor lives on the CPU, move it
+    /// with `Tensor::to_device` to run a model elsewhere.
+    #[cfg(feature = "candle")]
+    pub fn read_bands_tensor<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<candle_core::Tensor> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let reflectance = self.read_bands_reflectance(band_names, offset, window)?;
+        let (bands, width, height) = reflectance.dim();
+        // Arrays are indexed `[[x, y]]`, tensors are row-major.
+        let data = reflectance.permuted_axes([0, 2, 1]).iter().copied().collect::<Vec<_>>();
+        Ok(candle_core::Tensor::from_vec(
+            data,
+            (bands, height, width),
+            &candle_core::Device::Cpu,
+        )?)
+    }
+
+    /// Read bands as surface reflectance together with the scene classification
+    /// (`SCL`) of every pixel of the window, resampled onto the bands' grid.
+    pub fn read_bands_labeled<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array3<f32>, Array2<u8>)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.read_bands_labeled_with(band_names, offset, window, ClassResample::Nearest)
+    }
+
+    /// Like `read_bands_labeled`, but with the scene classification sampled
+    /// onto the bands' grid with `class_resample`.
+    pub fn read_bands_labeled_with<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        class_resample: ClassResample,
+    ) -> Result<(Array3<f32>, Array2<u8>)> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        self.metadata.require_level_2a("the SCL band")?;
+        let grid = self.bands_grid(&band_names)?;
+        let (classes, transform) = self.read_band_under_grid("SCL", &grid, offset, window)?;
+        let labels = resample_classes(classes.view(), transform, window, class_resample);
+        self.read_bands_reflectance(band_names, offset, window)
+            .map(|reflectance| (reflectance, labels))
+    }
+
+    /// Scene classification over a window of the raster's highest resolution
+    /// grid, sampled from the 20 m `SCL` band at the centres of its pixels.
+    /// Fails with `NotLevel2A` for level-1C products, which have none.
+    pub fn read_scl(
+        &self,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array2<u8>> {
+        self.metadata.require_level_2a("the SCL band")?;
+        let (classes, transform) = self.read_band_under_grid("SCL", &self.grid()?, offset, window)?;
+        Ok(resample_classes(classes.view(), transform, window, ClassResample::Nearest))
+    }
+
+    /// Cloud mask over a window of the raster's highest resolution grid, as
+    /// `read_scl` reads, flagging cloud shadows, medium and high probability
+    /// clouds and thin cirrus (classes 3, 8, 9 and 10), dilated by `buffer_px`
+    /// pixels of that grid to catch their fuzzy edges.
+    pub fn cloud_mask(
+        &self,
+        offset: (isize, isize),
+        window: (usize, usize),
+        buffer_px: usize,
+    ) -> Result<Array2<bool>> {
+        let clouds = self
+            .read_scl(offset, window)?
+            .mapv(|class| matches!(class, 3 | 8 | 9 | 10));
+        Ok(dilate(&clouds, buffer_px))
+    }
+
+    /// Number of clear pixels, i.e. vegetation, not vegetated or water (`SCL`
+    /// classes 4, 5 and 6), over a window of the raster's highest resolution
+    /// grid, as `read_scl` reads.
+    pub fn clear_pixel_count(
+        &self,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<usize> {
+        self.read_scl(offset, window).map(|scl| count_clear(scl.view()))
+    }
+
+    /// Read bands as surface reflectance z-scored with the `(mean, std)` of each
+    /// band in `stats`. Bands missing from `stats` are left unstandardized.
+    /// Fails with `InvalidBandStats` if a band's mean isn't finite or its std
+    /// isn't finite and positive, e.g. 0 for a constant band.
+    pub fn read_bands_standardized<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        stats: &HashMap<String, (f32, f32)>,
+    ) -> Result<Array3<f32>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        for &band_name in &band_names {
+            if let Some(&(mean, std)) = stats.get(band_name) {
+                if !(mean.is_finite() && std.is_finite() && std > 0.) {
+                    return Err(Sentinel2ArrayError::InvalidBandStats {
+                        band_name: band_name.to_string(),
+                        mean,
+                        std,
+                    });
+                }
+            }
+        }
+        let mut reflectance = self.read_bands_reflectance(band_names.clone(), offset, window)?;
+        for (band_name, mut band) in band_names.into_iter().zip(reflectance.outer_iter_mut()) {
+            match stats.get(band_name) {
+                Some(&(mean, std)) => band.mapv_inplace(|value| (value - mean) / std),
+                None => warn!("No statistics for band {band_name}, leaving it unstandardized."),
+            }
+        }
+        Ok(reflectance)
+    }
+
+    /// Read bands as surface reflectance corrected for terrain illumination.
+    ///
+    /// Elevations in meters are read from the first band of `dem`, bilinearly
+    /// warped onto the grid of the read window. Slope and aspect are derived
+    /// from them with Horn's method and combined with the sun angles,
+    /// interpolated at every pixel from the tile metadata's grids, into the
+    /// local illumination `cos(i)`. Each band is then C-corrected, with `c`
+    /// regressed over the window; when the window's illumination doesn't vary
+    /// enough to fit it, this degrades to the cosine correction. Pixels `dem`
+    /// doesn't cover are kept as read.
+    pub fn read_bands_topo_corrected<N: AsRef<str>>(
+        &self,
+        band_names: impl IntoIterator<Item = N>,
+        offset: (isize, isize),
+        window: (usize, usize),
+        dem: &Raster<impl Sensor>,
+    ) -> Result<Array3<f32>> {
+        let band_names = band_names.into_iter().collect::<Vec<_>>();
+        let band_names = band_names.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+        let transform = self.highest_resolution_transform(&band_names)?;
+        let grid = Grid {
+            crs: self.grid()?.crs,
+            transform: window_transform(transform, offset),
+            size: window,
+        };
+        let dem_band = dem
+            .band_names()
+            .into_iter()
+            .next()
+            .ok_or(Sentinel2ArrayError::NoBandsFound)?;
+        let elevations = dem
+            .read_bands_to_grid(&[dem_band.as_str()], &grid)?
+            .index_axis_move(Axis(0), 0);
+
+        let pixel_size = transform.m11;
+        let (sun_zenith, sun_azimuth) =
+            angles::read_sun_angle_grids(Path::new(self.metadata.0.description()))?;
+        let sun_zenith = sun_zenith.interpolate(offset, window, pixel_size).mapv(f64::to_radians);
+        let sun_azimuth = sun_azimuth.interpolate(offset, window, pixel_size).mapv(f64::to_radians);
+        let cos_incidence = cos_incidence(&elevations, pixel_size, &sun_zenith, &sun_azimuth);
+
+        let mut reflectance = self.read_bands_reflectance(band_names, offset, window)?;
+        for mut band in reflectance.outer_iter_mut() {
+            let c = c_correction_factor(band.view(), &cos_incidence);
+            Zip::from(&mut band)
+                .and(&cos_incidence)
+                .and(&sun_zenith)
+                .for_each(|value, &cos_i, &sun_zenith| {
+                    // Fully shadowed pixels can't be corrected, and `NaN` ones
+                    // lack elevations: keep them as read.
+                    if cos_i + c > f32::EPSILON {
+                        *value *= (sun_zenith.cos() as f32 + c) / (cos_i + c);
+                    }
+                });
+        }
+        Ok(reflectance)
+    }
+
+    /// Statistics of `band_name` GDAL already computed, without reading it.
+    /// `None` when there are none, so they have to be computed.
+    pub fn band_cached_statistics(&self, band_name: &str) -> Result<Option<BandStats>> {
+        self.band_metadata(band_name)?.cached_statistics()
+    }
+
+    /// Number of significant bits of the digital numbers, 12 for Sentinel-2
+    /// data stored in 16 bit integers. The widest among the bands telling it.
+    pub fn bit_depth(&self) -> Result<u8> {
+        self.band_names()
+            .into_iter()
+            .filter_map(|band_name| self.band_metadata(band_name).ok()?.bit_depth().ok())
+            .max()
+            .ok_or_else(|| Sentinel2ArrayError::MetadataKeyNotFound {
+                object_desc: self.metadata.0.description().into(),
+                key: "NBITS".into(),
+            })
+    }
+
+    /// Largest digital number the bands can hold, `2^bit_depth - 1`, to be
+    /// used as the top of contrast stretches instead of `u16::MAX`.
+    pub fn max_digital_number(&self) -> Result<u16> {
+        self.bit_depth()
+            .map(|bit_depth| ((1u32 << bit_depth) - 1).min(u16::MAX as u32) as u16)
+    }
+
+    /// True color thumbnail of the whole tile fitting within `max_dim` pixels,
+    /// shaped `(3, width, height)`. B4, B3 and B2 are read from their overviews
+    /// and stretched linearly from `[0, max_digital_number]` to `[0, 255]`.
+    pub fn thumbnail(&self, max_dim: usize) -> Result<Array3<u8>> {
+        let max_digital_number = self.max_digital_number()? as f32;
+        let channels = ["B4", "B3", "B2"]
+            .into_iter()
+            .map(|band_name| self.read_band_overview(band_name, max_dim))
+            .collect::<Result<Vec<_>>>()?;
+        let (width, height) = channels[0].dim();
+        Ok(Array3::from_shape_fn((3, width, height), |(c, x, y)| {
+            (channels[c][[x, y]] as f32 / max_digital_number * 255.).clamp(0., 255.) as u8
+        }))
+    }
+
+    /// True color image of a window of the 10 m grid, with B4, B3 and B2
+    /// each contrast stretched by `stretch` onto `[0, 255]`.
+    #[cfg(feature = "image")]
+    pub fn to_rgb_image(
+        &self,
+        offset: (isize, isize),
+        window: (usize, usize),
+        stretch: Stretch,
+    ) -> Result<image::RgbImage> {
+        let rgb = self.read_bands(["B4", "B3", "B2"], offset, window)?;
+        let bounds = rgb
+            .outer_iter()
+            .map(|band| stretch.bounds(band))
+            .collect::<Vec<_>>();
+        Ok(image::RgbImage::from_fn(window.0 as u32, window.1 as u32, |x, y| {
+            image::Rgb(std::array::from_fn(|c| {
+                let (low, high) = bounds[c];
+                let value = rgb[[c, x as usize, y as usize]] as f32;
+                if high > low {
+                    ((value - low) / (high - low) * 255.).clamp(0., 255.) as u8
+                } else {
+                    0
+                }
+            }))
+        }))
+    }
+
+    /// Mask rasters in the product's `QI_DATA`, e.g. `MSK_CLDPRB_20m` or
+    /// `MSK_DETFOO_B01`, sorted by name. Masks that can't be listed or opened
+    /// are left out with a warning.
+    pub fn masks(&self) -> Vec<MaskInfo> {
+        let product_path = Path::new(self.metadata.0.description());
+        let files = match product_files::list(product_path) {
+            Ok(files) => files,
+            Err(error) => {
+                warn!("Couldn't list masks of {}: {error}", product_path.display());
+                return Vec::new();
+            }
+        };
+        files
+            .into_iter()
+            .filter(|file| file.contains("QI_DATA/MSK_") && file.ends_with(".jp2"))
+            .filter_map(|file| {
+                let name = Path::new(&file).file_stem()?.to_string_lossy().into_owned();
+                let path = product_files::gdal_path(product_path, &file);
+                match gdal::Dataset::open(&path).and_then(|dataset| dataset.geo_transform()) {
+                    Ok(geo_transform) => Some(MaskInfo {
+                        name,
+                        resolution: geo_transform[1] as u8,
+                        path,
+                    }),
+                    Err(error) => {
+                        warn!("Couldn't open mask {name}: {error}");
+                        None
+                    }
+                }
+            })
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect()
+    }
+
+    /// Read the first band of mask `mask_name` over a window of its own grid.
+    pub fn read_mask(
+        &self,
+        mask_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<Array2<u8>> {
+        let mask = self
+            .masks()
+            .into_iter()
+            .find(|mask| mask.name == mask_name)
+            .ok_or_else(|| Sentinel2ArrayError::MaskNotFound(mask_name.into()))?;
+        RasterPathReader(&mask.path, 1)
+            .read_as_array(offset, window)
+            .map_err(Sentinel2ArrayError::RastersError)
+    }
+
+    /// Viewing zenith and azimuth angles of `band_name` in degrees, over a
+    /// window of the band's own pixel grid. They're bilinearly interpolated from
+    /// the band's tie-point grids in the tile metadata, merged over detectors,
+    /// and are `NaN` outside of the swath.
+    pub fn read_view_angles(
+        &self,
+        band_name: &str,
+        offset: (isize, isize),
+        window: (usize, usize),
+    ) -> Result<(Array2<f64>, Array2<f64>)> {
+        let pixel_size = self.highest_resolution_transform(&[band_name])?.m11;
+        let mut grids = angles::read_view_angle_grids(Path::new(self.metadata.0.description()))?;
+        let (zenith, azimuth) = grids
+            .remove(band_name)
+            .ok_or_else(|| Sentinel2ArrayError::ViewAnglesNotFound(band_name.into()))?;
+        Ok((
+            zenith.interpolate(offset, window, pixel_size),
+            azimuth.interpolate(offset, window, pixel_size),
+        ))
+    }
+}
+
+/// Bands of Sentinel-2 products, displayed as their `BANDNAME`. Level-1C
+/// products lack the last three, level-2A ones `B10`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum S2Band {
+    B1,
+    B2,
+    B3,
+    B4,
+    B5,
+    B6,
+    B7,
+    B8,
+    B8A,
+    B9,
+    B10,
+    B11,
+    B12,
+    /// Scene classification.
+    Scl,
+    /// Aerosol optical thickness.
+    Aot,
+    /// Water vapour.
+    Wvp,
+}
+
+impl S2Band {
+    /// Canonical name of the band.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::B1 => "B1",
+            Self::B2 => "B2",
+            Self::B3 => "B3",
+            Self::B4 => "B4",
+            Self::B5 => "B5",
+            Self::B6 => "B6",
+            Self::B7 => "B7",
+            Self::B8 => "B8",
+            Self::B8A => "B8A",
+            Self::B9 => "B9",
+            Self::B10 => "B10",
+            Self::B11 => "B11",
+            Self::B12 => "B12",
+            Self::Scl => "SCL",
+            Self::Aot => "AOT",
+            Self::Wvp => "WVP",
+        }
+    }
+
+    /// Band of canonical name `name`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::B1,
+            Self::B2,
+            Self::B3,
+            Self::B4,
+            Self::B5,
+            Self::B6,
+            Self::B7,
+            Self::B8,
+            Self::B8A,
+            Self::B9,
+            Self::B10,
+            Self::B11,
+            Self::B12,
+            Self::Scl,
+            Self::Aot,
+            Self::Wvp,
+        ]
+        .into_iter()
+        .find(|band| band.name() == name)
+    }
+
+    /// Central wavelength in nanometers of the spectral bands, averaged over
+    /// the S2A and S2B instruments.
+    pub fn central_wavelength(self) -> Option<f64> {
+        match self {
+            Self::B1 => Some(443.),
+            Self::B2 => Some(492.),
+            Self::B3 => Some(560.),
+            Self::B4 => Some(665.),
+            Self::B5 => Some(704.),
+            Self::B6 => Some(740.),
+            Self::B7 => Some(782.),
+            Self::B8 => Some(833.),
+            Self::B8A => Some(865.),
+            Self::B9 => Some(945.),
+            Self::B10 => Some(1375.),
+            Self::B11 => Some(1612.),
+            Self::B12 => Some(2191.),
+            Self::Scl | Self::Aot | Self::Wvp => None,
+        }
+    }
+}
+
+/// One line summary for logs, e.g. `T32TNS sensed 2023-05-01, EPSG:32632, 10980 x
+/// 10980 pixels, bands B1 (60 m), B11 (20 m), ... from <path>`. Parts missing
+/// from the metadata are left out.
+impl fmt::Display for Raster<Sentinel2> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Ok(tile_id) = self.metadata.tile_id() {
+            write!(f, "{tile_id} ")?;
+        }
+        let sensing_time = self.metadata.product_start_time().ok().and_then(|start_time| {
+            DateTime::parse_from_rfc3339(start_time.trim()).ok()
+        });
+        if let Some(sensing_time) = sensing_time {
+            write!(f, "sensed {}, ", sensing_time.date_naive())?;
+        }
+        let crs = self.projection().ok().and_then(|projection| {
+            let spatial_ref = gdal::spatial_ref::SpatialRef::from_wkt(projection).ok()?;
+            match (spatial_ref.auth_name(), spatial_ref.auth_code()) {
+                (Ok(name), Ok(code)) => Some(format!("{name}:{code}")),
+                _ => spatial_ref.name().ok(),
+            }
+        });
+        if let Some(crs) = crs {
+            write!(f, "{crs}, ")?;
+        }
+        if let Ok((width, height)) = self.size() {
+            write!(f, "{width} x {height} pixels, ")?;
+        }
+        let bands = self
+            .band_names()
+            .into_iter()
+            .map(|band_name| match self.band_resolution(band_name) {
+                Ok(resolution) => format!("{band_name} ({resolution} m)"),
+                Err(_) => band_name.clone(),
+            })
+            .join(", ");
+        write!(f, "bands {bands} from {}", self.metadata.0.description())
+    }
+}
+
+/// Identity of a scene, which rasters compare and hash by.
+#[derive(PartialEq, Eq, Hash)]
+enum SceneIdentity<'a> {
+    Product {
+        tile_id: String,
+        start_time: &'a str,
+        processing_baseline: &'a str,
+    },
+    /// GDAL name of the product, as it was opened.
+    Path(&'a str),
+}
+
+impl Raster<Sentinel2> {
+    fn scene_identity(&self) -> SceneIdentity<'_> {
+        let metadata = &self.metadata.0;
+        match (
+            self.metadata.tile_id(),
+            metadata.get("PRODUCT_START_TIME"),
+            metadata.get("PROCESSING_BASELINE"),
+        ) {
+            (Ok(tile_id), Ok(start_time), Ok(processing_baseline)) => SceneIdentity::Product {
+                tile_id,
+                start_time: start_time.trim(),
+                processing_baseline: processing_baseline.trim(),
+            },
+            _ => SceneIdentity::Path(metadata.description()),
+        }
+    }
+}
+
+/// Rasters are the same scene if their products share the tile ID of their
+/// `PRODUCT_URI`, their `PRODUCT_START_TIME` and `PROCESSING_BASELINE`, or,
+/// for products missing any of those, the GDAL name they were opened from.
+/// Bands, their metadata and read settings aren't compared: rasters of one
+/// product with different bands selected are equal. Neither are the datasets
+/// band groups cache, which `clippy::mutable_key_type` flags in hash sets.
+impl PartialEq for Raster<Sentinel2> {
+    fn eq(&self, other: &Self) -> bool {
+        self.scene_identity() == other.scene_identity()
+    }
+}
+
+impl Eq for Raster<Sentinel2> {}
+
+impl Hash for Raster<Sentinel2> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.scene_identity().hash(state);
+    }
+}
+
+impl fmt::Display for S2Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Number of `SCL` classes in `scl` that are clear ground.
+pub(crate) fn count_clear(scl: ArrayView2<u8>) -> usize {
+    scl.iter().filter(|&&class| matches!(class, 4..=6)).count()
+}
+
+/// How class maps, e.g. the `SCL` band, get sampled onto a read grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClassResample {
+    /// Class of the source pixel under the centre of the read pixel.
+    #[default]
+    Nearest,
+    /// Most common class among the source pixels the read pixel overlaps, the
+    /// lowest one on ties. Only differs from `Nearest` when downsampling.
+    Majority,
+}
+
+/// Sample `classes` onto `window` of a grid whose pixels map to pixels of
+/// `classes` through `transform`.
+pub(crate) fn resample_classes(
+    classes: ArrayView2<u16>,
+    transform: PixelTransform,
+    window: (usize, usize),
+    class_resample: ClassResample,
+) -> Array2<u8> {
+    let (width, height) = classes.dim();
+    let clamp = |coordinate: f64, extent: usize| (coordinate.max(0.) as usize).min(extent - 1);
+    // Source pixels between two, possibly fractional, coordinates along an axis.
+    let covered = |start: f64, end: f64, extent: usize| {
+        let first = clamp(start.min(end).floor(), extent);
+        let last = clamp(start.max(end).ceil() - 1., extent).max(first);
+        first..=last
+    };
+    Array2::from_shape_fn(window, |(x, y)| {
+        let class = match class_resample {
+            ClassResample::Nearest => {
+                let centre =
+                    transform.transform_point(&Point2::new(x as f64 + 0.5, y as f64 + 0.5));
+                classes[[clamp(centre.x, width), clamp(centre.y, height)]]
+            }
+            ClassResample::Majority => {
+                let start = transform.transform_point(&Point2::new(x as f64, y as f64));
+                let end = transform.transform_point(&Point2::new(x as f64 + 1., y as f64 + 1.));
+                covered(start.x, end.x, width)
+                    .cartesian_product(covered(start.y, end.y, height))
+                    .map(|index| classes[index])
+                    .sorted_unstable()
+                    .dedup_with_count()
+                    .max_by_key(|&(count, class)| (count, Reverse(class)))
+                    .map_or(0, |(_, class)| class)
+            }
+        };
+        class as u8
+    })
+}
+
+/// Morphological dilation of `mask` by a square of `radius` pixels around
+/// every pixel, shrunk at the window edges.
+pub(crate) fn dilate(mask: &Array2<bool>, radius: usize) -> Array2<bool> {
+    let (width, height) = mask.dim();
+    let neighbours = |index: usize, extent: usize| {
+        index.saturating_sub(radius)..(index + radius + 1).min(extent)
+    };
+    // Squares are separable, dilate along x and then along y.
+    let along_x = Array2::from_shape_fn((width, height), |(x, y)| {
+        neighbours(x, width).any(|x| mask[[x, y]])
+    });
+    Array2::from_shape_fn((width, height), |(x, y)| {
+        neighbours(y, height).any(|y| along_x[[x, y]])
+    })
+}
+
+/// Cosine of the solar incidence angle on the terrain surface of every pixel.
+fn cos_incidence(
+    dem: &Array2<f32>,
+    pixel_size: f64,
+    sun_zenith: &Array2<f64>,
+    sun_azimuth: &Array2<f64>,
+) -> Array2<f32> {
+    let (width, height) = dem.dim();
+    // Elevation of the neighbour at (x + dx, y + dy), clamped at the window edges.
+    let z = |x: usize, y: usize, dx: isize, dy: isize| {
+        let x = (x as isize + dx).clamp(0, width as isize - 1) as usize;
+        let y = (y as isize + dy).clamp(0, height as isize - 1) as usize;
+        dem[[x, y]] as f64
+    };
+    Array2::from_shape_fn((width, height), |(x, y)| {
+        // Horn's method, y grows southwards.
+        let dz_east = ((z(x, y, 1, -1) + 2. * z(x, y, 1, 0) + z(x, y, 1, 1))
+            - (z(x, y, -1, -1) + 2. * z(x, y, -1, 0) + z(x, y, -1, 1)))
+            / (8. * pixel_size);
+        let dz_north = ((z(x, y, -1, -1) + 2. * z(x, y, 0, -1) + z(x, y, 1, -1))
+            - (z(x, y, -1, 1) + 2. * z(x, y, 0, 1) + z(x, y, 1, 1)))
+            / (8. * pixel_size);
+        let slope = dz_east.hypot(dz_north).atan();
+        let aspect = (-dz_east).atan2(-dz_north);
+        let (sun_zenith, sun_azimuth) = (sun_zenith[[x, y]], sun_azimuth[[x, y]]);
+        (sun_zenith.cos() * slope.cos()
+            + sun_zenith.sin() * slope.sin() * (sun_azimuth - aspect).cos()) as f32
+    })
+}
+
+/// `c` of the C-correction, the intercept over slope of the linear regression
+/// of reflectance on `cos(i)` over the pixels where both are finite. Zero
+/// (cosine correction) if it can't be fit.
+fn c_correction_factor(band: ArrayView2<f32>, cos_incidence: &Array2<f32>) -> f32 {
+    let (n, sum_x, sum_y, sum_xx, sum_xy) = Zip::from(band).and(cos_incidence).fold(
+        (0., 0., 0., 0., 0.),
+        |(n, sum_x, sum_y, sum_xx, sum_xy), &y, &x| {
+            if !(x.is_finite() && y.is_finite()) {
+                return (n, sum_x, sum_y, sum_xx, sum_xy);
+            }
+            let (x, y) = (x as f64, y as f64);
+            (n + 1., sum_x + x, sum_y + y, sum_xx + x * x, sum_xy + x * y)
+        },
+    );
+    if n == 0. {
+        return 0.;
+    }
+    let variance_x = sum_xx - sum_x * sum_x / n;
+    if variance_x <= f64::EPSILON {
+        return 0.;
+    }
+    let slope = (sum_xy - sum_x * sum_y / n) / variance_x;
+    let intercept = (sum_y - slope * sum_x) / n;
+    if slope > 0. {
+        (intercept / slope) as f32
+    } else {
+        0.
+    }
+}
+
+/// Mask raster of a product's `QI_DATA`, see [`Raster::masks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskInfo {
+    pub name: String,
+    pub resolution: u8,
+    /// GDAL path of the mask's file.
+    pub path: String,
 }
 
 #[derive(Debug)]
@@ -112,11 +1525,158 @@ pub struct GeometryWCRS {
     pub crs: String,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct BandMetadata(Metadata);
 
 impl BandMetadata {
     pub fn new(description: String) -> Self {
         Self(Metadata::new(description))
     }
+
+    pub fn cached_statistics(&self) -> Result<Option<BandStats>> {
+        self.0.cached_statistics()
+    }
+
+    /// Number of significant bits of the band's digital numbers.
+    pub fn bit_depth(&self) -> Result<u8> {
+        self.0.get_parsed("NBITS")
+    }
+
+    /// Pixel size in meters the band's `SPATIAL_RESOLUTION` item tells, if it
+    /// has one.
+    pub fn spatial_resolution(&self) -> Result<Option<f64>> {
+        match self.0.get("SPATIAL_RESOLUTION") {
+            Ok(_) => self.0.get_parsed("SPATIAL_RESOLUTION").map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Central wavelength in nanometers the band's `WAVELENGTH` item tells,
+    /// if it has one.
+    pub fn wavelength(&self) -> Result<Option<f64>> {
+        match self.0.get("WAVELENGTH") {
+            Ok(_) => self.0.get_parsed("WAVELENGTH").map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Exo-atmospheric solar irradiance of the band, in W/m²/µm.
+    pub fn solar_irradiance(&self) -> Result<f32> {
+        self.0.get_parsed("SOLAR_IRRADIANCE")
+    }
+}
+
+impl ReadBuilder<'_, Sentinel2> {
+    /// Like `execute_georef`, with the digital numbers converted into
+    /// reflectance, see [`Reflectance::from_raster`].
+    pub fn execute_reflectance(self) -> Result<ReadResult<f32>> {
+        let reflectance = Reflectance::from_raster(self.raster())?;
+        self.execute_converted(&reflectance)
+    }
+}
+
+/// Converts digital numbers into reflectance.
+///
+/// Bands with a GDAL scale and offset are calibrated with them, as
+/// `value * scale + offset`, which takes precedence over the product's
+/// quantification. The others are offset by their band's entry of
+/// [`RasterMetadata::boa_offsets`], or `BOA_ADD_OFFSET` if they have none, and
+/// divided by `BOA_QUANTIFICATION_VALUE`.
+#[derive(Debug, Clone)]
+pub struct Reflectance {
+    quantification_value: f64,
+    add_offset: f64,
+    add_offsets: HashMap<String, f64>,
+    calibrations: HashMap<String, (f64, f64)>,
+}
+
+impl Reflectance {
+    /// Converter offsetting and dividing all bands by the add offsets and the
+    /// quantification value of `metadata`.
+    pub fn new(metadata: &RasterMetadata) -> Result<Self> {
+        Ok(Self {
+            quantification_value: metadata.boa_quantification_value()?.into(),
+            add_offset: metadata.boa_add_offset()?.into(),
+            add_offsets: metadata
+                .boa_offsets()?
+                .into_iter()
+                .map(|(band_name, offset)| (band_name, offset.into()))
+                .collect(),
+            calibrations: HashMap::new(),
+        })
+    }
+
+    /// Converter for the bands of `raster`, with their GDAL calibrations.
+    pub fn from_raster(raster: &Raster<Sentinel2>) -> Result<Self> {
+        let mut reflectance = Self::new(&raster.metadata)?;
+        for band_name in raster.band_names() {
+            if let Some((scale, offset)) = raster.band_calibration(band_name)? {
+                reflectance = reflectance.with_calibration(band_name, scale, offset);
+            }
+        }
+        Ok(reflectance)
+    }
+
+    pub fn with_calibration(mut self, band_name: &str, scale: f64, offset: f64) -> Self {
+        self.calibrations.insert(band_name.into(), (scale, offset));
+        self
+    }
+
+    pub fn convert_f64(&self, band_name: &str, value: u16) -> f64 {
+        match self.calibrations.get(band_name) {
+            Some((scale, offset)) => value as f64 * scale + offset,
+            None => {
+                let add_offset = self.add_offsets.get(band_name).unwrap_or(&self.add_offset);
+                (value as f64 + add_offset) / self.quantification_value
+            }
+        }
+    }
+}
+
+impl PixelConverter for Reflectance {
+    fn convert(&self, band_name: &str, value: u16) -> f32 {
+        self.convert_f64(band_name, value) as f32
+    }
+}
+
+/// Converts digital numbers into radiance in W/m²/sr/µm, inverting
+/// `ρ = π L / (E U cos θ)` with the band's solar irradiance `E`, the earth-sun
+/// distance correction `U` and the mean sun zenith `θ`. Bands without a known
+/// solar irradiance convert to `NaN`.
+#[derive(Debug, Clone)]
+pub struct Radiance {
+    reflectance: Reflectance,
+    /// `U cos θ / π`, shared by all bands.
+    scale: f32,
+    solar_irradiances: HashMap<String, f32>,
+}
+
+impl Radiance {
+    pub fn new(raster: &Raster<Sentinel2>) -> Result<Self> {
+        let (sun_zenith, _) = raster.metadata.mean_sun_angles()?;
+        let u = raster.metadata.reflectance_conversion_u()?;
+        let solar_irradiances = raster
+            .band_names()
+            .into_iter()
+            .filter_map(|band_name| {
+                let band_metadata = raster.band_metadata(band_name).ok()?;
+                Some((band_name.clone(), band_metadata.solar_irradiance().ok()?))
+            })
+            .collect();
+        Ok(Self {
+            reflectance: Reflectance::from_raster(raster)?,
+            scale: u * sun_zenith.to_radians().cos() as f32 / std::f32::consts::PI,
+            solar_irradiances,
+        })
+    }
+}
+
+impl PixelConverter for Radiance {
+    fn convert(&self, band_name: &str, value: u16) -> f32 {
+        self.solar_irradiances
+            .get(band_name)
+            .map_or(f32::NAN, |solar_irradiance| {
+                self.reflectance.convert(band_name, value) * solar_irradiance * self.scale
+            })
+    }
 }
@@ -0,0 +1,208 @@
+//! Angle tie-point grids of the tile metadata (`MTD_TL.xml`), which GDAL's
+//! Sentinel2 driver doesn't expose.
+
+use ndarray::Array2;
+use std::{collections::HashMap, path::Path};
+
+use super::product_files;
+use crate::errors::{Result, Sentinel2ArrayError};
+
+/// Band names in the order of the `bandId` attributes of the tile metadata.
+pub(super) const BAND_IDS: [&str; 13] = [
+    "B1", "B2", "B3", "B4", "B5", "B6", "B7", "B8", "B8A", "B9", "B10", "B11", "B12",
+];
+
+/// Angles in degrees, sampled every `step` meters from the tile's upper left corner.
+#[derive(Debug)]
+pub struct AngleGrid {
+    step: (f64, f64),
+    /// Indexed `[[col, row]]`, `NaN` outside of the detectors' footprint.
+    values: Array2<f64>,
+    /// Whether angles wrap around at 360°, as azimuths do.
+    circular: bool,
+}
+
+impl AngleGrid {
+    fn parse(node: roxmltree::Node, circular: bool) -> Option<Self> {
+        let child_text = |tag_name| {
+            node.children()
+                .find(|child| child.has_tag_name(tag_name))
+                .and_then(|child| child.text())
+        };
+        let step: (f64, f64) = (
+            child_text("COL_STEP")?.trim().parse().ok()?,
+            child_text("ROW_STEP")?.trim().parse().ok()?,
+        );
+        let rows = node
+            .descendants()
+            .filter(|descendant| descendant.has_tag_name("VALUES"))
+            .map(|values| {
+                values
+                    .text()?
+                    .split_whitespace()
+                    .map(|value| value.parse().ok())
+                    .collect::<Option<Vec<f64>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let n_cols = rows.first()?.len();
+        if rows.iter().any(|row| row.len() != n_cols) {
+            return None;
+        }
+        let values = Array2::from_shape_fn((n_cols, rows.len()), |(col, row)| rows[row][col]);
+        Some(Self {
+            step,
+            values,
+            circular,
+        })
+    }
+
+    /// Merge the grids of overlapping detectors, averaging where more than one
+    /// of them sees the same node.
+    fn merge(grids: Vec<Self>) -> Option<Self> {
+        let first = grids.first()?;
+        if grids
+            .iter()
+            .any(|grid| grid.values.dim() != first.values.dim())
+        {
+            return None;
+        }
+        let values = Array2::from_shape_fn(first.values.dim(), |index| {
+            weighted_mean(
+                grids.iter().map(|grid| (grid.values[index], 1.)),
+                first.circular,
+            )
+        });
+        Some(Self { values, ..*first })
+    }
+
+    /// Bilinearly interpolate the grid at the centres of the pixels of a window
+    /// with the given offset and size, on a pixel grid of `pixel_size` meters
+    /// aligned with the tile's upper left corner.
+    pub fn interpolate(
+        &self,
+        offset: (isize, isize),
+        window: (usize, usize),
+        pixel_size: f64,
+    ) -> Array2<f64> {
+        let (n_cols, n_rows) = self.values.dim();
+        // Surrounding nodes and the weight of the far one along an axis.
+        let nodes = |pixel: isize, step: f64, n_nodes: usize| {
+            let position =
+                ((pixel as f64 + 0.5) * pixel_size / step).clamp(0., (n_nodes - 1) as f64);
+            let near = (position.floor() as usize).min(n_nodes.saturating_sub(2));
+            let far = (near + 1).min(n_nodes - 1);
+            (near, far, position - near as f64)
+        };
+        Array2::from_shape_fn(window, |(x, y)| {
+            let (col, next_col, col_weight) = nodes(offset.0 + x as isize, self.step.0, n_cols);
+            let (row, next_row, row_weight) = nodes(offset.1 + y as isize, self.step.1, n_rows);
+            weighted_mean(
+                [
+                    (
+                        self.values[[col, row]],
+                        (1. - col_weight) * (1. - row_weight),
+                    ),
+                    (self.values[[next_col, row]], col_weight * (1. - row_weight)),
+                    (self.values[[col, next_row]], (1. - col_weight) * row_weight),
+                    (self.values[[next_col, next_row]], col_weight * row_weight),
+                ]
+                .into_iter(),
+                self.circular,
+            )
+        })
+    }
+}
+
+/// Weighted mean of the non `NaN` angles, `NaN` if there are none.
+fn weighted_mean(angles: impl Iterator<Item = (f64, f64)>, circular: bool) -> f64 {
+    let (sum, sum_sin, sum_cos, sum_weights) = angles
+        .filter(|(angle, weight)| !angle.is_nan() && *weight > 0.)
+        .fold(
+            (0., 0., 0., 0.),
+            |(sum, sum_sin, sum_cos, sum_weights), (angle, weight)| {
+                let radians = f64::to_radians(angle);
+                (
+                    sum + angle * weight,
+                    sum_sin + radians.sin() * weight,
+                    sum_cos + radians.cos() * weight,
+                    sum_weights + weight,
+                )
+            },
+        );
+    if sum_weights == 0. {
+        f64::NAN
+    } else if circular {
+        sum_sin.atan2(sum_cos).to_degrees().rem_euclid(360.)
+    } else {
+        sum / sum_weights
+    }
+}
+
+/// Viewing zenith and azimuth grids of every band listed in the tile metadata
+/// of the product at `product_path`, with detectors merged.
+pub fn read_view_angle_grids(
+    product_path: &Path,
+) -> Result<HashMap<String, (AngleGrid, AngleGrid)>> {
+    let tile_metadata = read_tile_metadata(product_path)?;
+    let document = roxmltree::Document::parse(&tile_metadata)?;
+    let mut grids: HashMap<&str, (Vec<AngleGrid>, Vec<AngleGrid>)> = HashMap::new();
+    for node in document
+        .descendants()
+        .filter(|node| node.has_tag_name("Viewing_Incidence_Angles_Grids"))
+    {
+        let Some(band_name) = node
+            .attribute("bandId")
+            .and_then(|band_id| BAND_IDS.get(band_id.parse::<usize>().ok()?))
+        else {
+            continue;
+        };
+        let grid = |tag_name, circular| {
+            node.children()
+                .find(|child| child.has_tag_name(tag_name))
+                .and_then(|child| AngleGrid::parse(child, circular))
+                .ok_or_else(|| Sentinel2ArrayError::AngleGridNotParsable(band_name.to_string()))
+        };
+        let (zeniths, azimuths) = grids.entry(band_name).or_default();
+        zeniths.push(grid("Zenith", false)?);
+        azimuths.push(grid("Azimuth", true)?);
+    }
+    grids
+        .into_iter()
+        .map(|(band_name, (zeniths, azimuths))| {
+            AngleGrid::merge(zeniths)
+                .zip(AngleGrid::merge(azimuths))
+                .map(|merged| (band_name.to_string(), merged))
+                .ok_or_else(|| Sentinel2ArrayError::AngleGridNotParsable(band_name.to_string()))
+        })
+        .collect()
+}
+
+/// Sun zenith and azimuth grids of the tile metadata of the product at
+/// `product_path`.
+pub fn read_sun_angle_grids(product_path: &Path) -> Result<(AngleGrid, AngleGrid)> {
+    let tile_metadata = read_tile_metadata(product_path)?;
+    let document = roxmltree::Document::parse(&tile_metadata)?;
+    let node = document
+        .descendants()
+        .find(|node| node.has_tag_name("Sun_Angles_Grid"));
+    let grid = |tag_name, circular| {
+        node?
+            .children()
+            .find(|child| child.has_tag_name(tag_name))
+            .and_then(|child| AngleGrid::parse(child, circular))
+    };
+    grid("Zenith", false)
+        .zip(grid("Azimuth", true))
+        .ok_or_else(|| Sentinel2ArrayError::AngleGridNotParsable("sun".into()))
+}
+
+/// Content of `GRANULE/*/MTD_TL.xml` of the product.
+fn read_tile_metadata(product_path: &Path) -> Result<String> {
+    let tile_metadata = product_files::list(product_path)?
+        .into_iter()
+        .find(|file| file.contains("GRANULE/") && file.ends_with("MTD_TL.xml"))
+        .ok_or_else(|| {
+            Sentinel2ArrayError::TileMetadataNotFound(product_path.display().to_string())
+        })?;
+    product_files::read_to_string(product_path, &tile_metadata)
+}
@@ -0,0 +1,77 @@
+//! Files of a product beyond the ones GDAL's Sentinel2 driver reads, for a
+//! product given as a `.SAFE` directory, its zip file or its metadata file.
+
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::errors::Result;
+
+fn is_zip(product_path: &Path) -> bool {
+    product_path
+        .extension()
+        .is_some_and(|extension| extension == "zip")
+}
+
+fn product_dir(product_path: &Path) -> &Path {
+    if product_path.is_dir() {
+        product_path
+    } else {
+        product_path.parent().unwrap_or(Path::new("."))
+    }
+}
+
+/// Product metadata file (`MTD_MSIL2A.xml` or `MTD_MSIL1C.xml`) at the top
+/// of the `.SAFE` directory `product_dir`.
+pub fn metadata_file(product_dir: &Path) -> Option<PathBuf> {
+    ["MTD_MSIL2A.xml", "MTD_MSIL1C.xml"]
+        .into_iter()
+        .map(|file_name| product_dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+/// Paths of the product's files, relative to its zip file or directory and
+/// `/` separated.
+pub fn list(product_path: &Path) -> Result<Vec<String>> {
+    if is_zip(product_path) {
+        let archive = zip::ZipArchive::new(File::open(product_path)?)?;
+        return Ok(archive.file_names().map(String::from).collect());
+    }
+    let product_dir = product_dir(product_path);
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::from(product_dir)];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(relative) = path.strip_prefix(product_dir) {
+                let components = relative.iter().map(|part| part.to_string_lossy());
+                files.push(components.collect::<Vec<_>>().join("/"));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Content of the product's `file`, a path given by `list`.
+pub fn read_to_string(product_path: &Path, file: &str) -> Result<String> {
+    if is_zip(product_path) {
+        let mut archive = zip::ZipArchive::new(File::open(product_path)?)?;
+        let mut content = String::new();
+        archive.by_name(file)?.read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    Ok(fs::read_to_string(product_dir(product_path).join(file))?)
+}
+
+/// Path GDAL opens the product's `file`, a path given by `list`, with.
+pub fn gdal_path(product_path: &Path, file: &str) -> String {
+    if is_zip(product_path) {
+        format!("/vsizip/{}/{file}", product_path.display())
+    } else {
+        product_dir(product_path).join(file).display().to_string()
+    }
+}
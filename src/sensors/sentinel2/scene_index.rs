@@ -0,0 +1,131 @@
+//! Parsed band layout of a product saved to a JSON file, to open the product
+//! again without GDAL scanning its subdatasets and their bands.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use super::{BandMetadata, RasterMetadata, Sentinel2};
+use crate::{
+    components::{
+        band::{BandGroup, BandGroupLayout, BandInfo, Bands},
+        metadata::Metadata,
+        raster::Raster,
+        reader::DatasetReader,
+    },
+    errors::{Result, Sentinel2ArrayError},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SceneIndex {
+    /// Product level metadata, described by the GDAL name of the product.
+    metadata: Metadata,
+    band_groups: Vec<BandGroupLayout>,
+    bands: Vec<BandEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BandEntry {
+    name: String,
+    /// Index of the band's group in `SceneIndex::band_groups`.
+    group: usize,
+    /// 1 based index of the band in its group's dataset.
+    index: usize,
+    calibration: Option<(f64, f64)>,
+    metadata: Metadata,
+}
+
+impl Raster<Sentinel2> {
+    /// Save the band layout and metadata of the raster to `index_path`, for
+    /// [`Raster::from_index`] to open the product again.
+    pub fn save_index<P: AsRef<Path>>(&self, index_path: P) -> Result<()> {
+        let mut group_indices = HashMap::new();
+        let mut band_groups = Vec::new();
+        let bands = self
+            .band_infos()
+            .iter_sorted()
+            .map(|(band_name, band_info)| {
+                let group = *group_indices
+                    .entry(Arc::as_ptr(band_info.group()))
+                    .or_insert_with(|| {
+                        band_groups.push(band_info.group().layout());
+                        band_groups.len() - 1
+                    });
+                BandEntry {
+                    name: band_name.clone(),
+                    group,
+                    index: band_info.index(),
+                    calibration: band_info.calibration(),
+                    metadata: band_info.metadata().0.clone(),
+                }
+            })
+            .collect();
+        let index = SceneIndex {
+            metadata: self.metadata.0.clone(),
+            band_groups,
+            bands,
+        };
+        fs::write(index_path, serde_json::to_string(&index)?)?;
+        Ok(())
+    }
+
+    /// Open the product at `data_path` with the band layout `save_index` saved
+    /// to `index_path`, without GDAL scanning it. The product may have moved
+    /// since: the GDAL names of its subdatasets get rewritten to `data_path`.
+    ///
+    /// Fails with `IndexDataNotFound` if there's no product at `data_path`,
+    /// which must be on the local file system. The index isn't checked against
+    /// the product's content, an index of another product reads garbage.
+    pub fn from_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        index_path: P,
+        data_path: Q,
+    ) -> Result<Self> {
+        let index: SceneIndex = serde_json::from_str(&fs::read_to_string(index_path)?)?;
+        let dataset_path = Sentinel2::dataset_path(data_path.as_ref())?;
+        if !dataset_path.exists() {
+            return Err(Sentinel2ArrayError::IndexDataNotFound(
+                dataset_path.display().to_string(),
+            ));
+        }
+        let indexed_path = index.metadata.description();
+        let dataset_path = dataset_path.display().to_string();
+        let band_groups = index
+            .band_groups
+            .into_iter()
+            .map(|mut layout| {
+                layout.gdal_dataset_path =
+                    layout.gdal_dataset_path.replace(indexed_path, &dataset_path);
+                BandGroup::from_layout(layout).map(Arc::new)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let bands = index
+            .bands
+            .into_iter()
+            .map(|band| {
+                let band_group = band_groups.get(band.group).ok_or_else(|| {
+                    Sentinel2ArrayError::InvalidIndex {
+                        band_name: band.name.clone(),
+                        group: band.group,
+                        band_groups: band_groups.len(),
+                    }
+                })?;
+                if !(1..=band_group.band_count()).contains(&band.index) {
+                    return Err(Sentinel2ArrayError::InvalidIndexBand {
+                        band_name: band.name,
+                        index: band.index,
+                        bands: band_group.band_count(),
+                    });
+                }
+                let band_info =
+                    BandInfo::new(Arc::clone(band_group), band.index, BandMetadata(band.metadata))
+                        .with_calibration(band.calibration);
+                Ok((band.name, band_info))
+            })
+            .collect::<Result<Bands<BandMetadata>>>()?;
+
+        let mut metadata = RasterMetadata::new(dataset_path);
+        for (key, value) in index.metadata.iter() {
+            metadata.0.insert(key.clone(), value.clone());
+        }
+        Ok(Raster::new(bands, metadata))
+    }
+}